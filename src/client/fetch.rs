@@ -0,0 +1,116 @@
+//! Stream of `FETCH` responses
+
+use crate::{
+    error::{Error, Result},
+    fetch::Fetch,
+    imapconnection::{ImapConnection, Responses},
+    parser::{
+        parse_response,
+        types::{ImapResponse, ImapResult, MessageData, UntaggedResponse},
+    },
+    types::MsgAtt,
+};
+use futures::Stream;
+use log::warn;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Messages received from `FETCH` one by one as they arrive.
+/// The stream ends with the completion of the command. A message which
+/// can't be parsed is yielded as [Error::Parser] and the stream goes on.
+/// It borrows the client, so the next command can be sent only after
+/// the stream is drained or dropped
+#[derive(Debug)]
+pub struct FetchStream<'a, S> {
+    // Responses of other commands would wait behind the undrained ones
+    _connection: &'a mut ImapConnection<S>,
+    responses: Responses,
+    finished: bool,
+}
+
+impl<'a, S> FetchStream<'a, S> {
+    pub(crate) fn new(connection: &'a mut ImapConnection<S>, responses: Responses) -> Self {
+        Self {
+            _connection: connection,
+            responses,
+            finished: false,
+        }
+    }
+}
+
+impl<S> Stream for FetchStream<'_, S> {
+    type Item = Result<Fetch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        while !this.finished {
+            let line = match this.responses.poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(line)) => line,
+                Poll::Ready(Err(e)) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            };
+
+            let response = match parse_response(&line) {
+                Ok((_, response)) => response,
+                Err(_) if line.starts_with(format!("{} ", this.responses.tag()).as_bytes()) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(Error::Parser(line.to_vec()))));
+                }
+                // The caller must know the results are incomplete
+                Err(_) if is_fetch(&line) => {
+                    return Poll::Ready(Some(Err(Error::Parser(line.to_vec()))));
+                }
+                Err(_) => {
+                    warn!("Skip unknown response: {:?}", line);
+                    continue;
+                }
+            };
+
+            match response {
+                ImapResponse::Data(UntaggedResponse::Message(MessageData::Fetch(
+                    n,
+                    attributes,
+                ))) => {
+                    let attributes = attributes.iter().map(MsgAtt::from).collect();
+                    return Poll::Ready(Some(Ok(Fetch::new(n, attributes))));
+                }
+                ImapResponse::Data(UntaggedResponse::RespBye(bye)) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(Error::Bye(bye.resp.text.to_owned()))));
+                }
+                ImapResponse::Response(done) if done.tag == *this.responses.tag() => {
+                    this.finished = true;
+                    let text = done.resp.text.text.to_owned();
+                    match done.resp.status {
+                        ImapResult::Ok => {}
+                        ImapResult::No => return Poll::Ready(Some(Err(Error::No(text)))),
+                        ImapResult::Bad => return Poll::Ready(Some(Err(Error::Bad(text)))),
+                    }
+                }
+                // Other updates of the mailbox are not interesting here
+                ImapResponse::Data(data) => this.responses.forward(&data),
+                _ => {}
+            }
+        }
+
+        Poll::Ready(None)
+    }
+}
+
+// "*" SP nz-number SP "FETCH"
+fn is_fetch(line: &[u8]) -> bool {
+    let rest = match line.strip_prefix(b"* ") {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+    let keyword = &rest[digits..];
+
+    digits > 0 && keyword.len() >= 6 && keyword[..6].eq_ignore_ascii_case(b" FETCH")
+}
@@ -0,0 +1,263 @@
+//! `IDLE` command, RFC 2177
+
+use crate::{
+    command::Command,
+    error::{Error, Result},
+    imapconnection::{ImapConnection, Responses},
+    parser::{
+        parse_response,
+        types::{ImapResponse, ImapResult, UntaggedResponse},
+    },
+    tag::Tag,
+    types::{MailboxData, MessageData, SequenceSet},
+};
+use futures::{future::BoxFuture, Future, Stream};
+use log::warn;
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time::{sleep, Instant, Sleep},
+};
+
+/// Servers may log out the client after 30 minutes of inactivity,
+/// so `IDLE` is re-issued a bit earlier
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(29 * 60);
+
+/// Update received while idling
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum IdleEvent {
+    /// Mailbox status update, e.g. the new number of messages in `EXISTS`
+    Mailbox(MailboxData),
+    /// Message status update, `EXPUNGE` or `FETCH` with changed flags
+    Message(MessageData),
+    /// Unique identifiers of the expunged messages, `VANISHED` replaces
+    /// `EXPUNGE` once QRESYNC is enabled
+    Vanished(SequenceSet),
+}
+
+/// `IDLE` in progress, a stream of updates from the server. The command is
+/// re-issued automatically every [IDLE_TIMEOUT]. The stream ends when the
+/// server terminates `IDLE`, call [Idle::done] to terminate it by the client.
+/// If the stream is dropped instead, `DONE` is sent before the next command
+/// and updates received until the completion go to
+/// [Client::unsolicited][crate::client::Client::unsolicited]
+pub struct Idle<'a, S> {
+    state: State<'a, S>,
+    // Updates received while `IDLE` was re-issued
+    events: VecDeque<IdleEvent>,
+    timeout: Duration,
+    sleep: Pin<Box<Sleep>>,
+}
+
+enum State<'a, S> {
+    Idling(Inner<'a, S>),
+    Restarting(BoxFuture<'a, Result<(Inner<'a, S>, Vec<IdleEvent>)>>),
+    Finished,
+}
+
+impl<S> std::fmt::Debug for Idle<'_, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self.state {
+            State::Idling(_) => "Idling",
+            State::Restarting(_) => "Restarting",
+            State::Finished => "Finished",
+        };
+
+        f.debug_struct("Idle")
+            .field("state", &state)
+            .field("events", &self.events)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl<'a, S> Idle<'a, S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    pub(crate) async fn start(connection: &'a mut ImapConnection<S>) -> Result<Idle<'a, S>> {
+        let inner = Inner::start(connection).await?;
+
+        Ok(Self {
+            state: State::Idling(inner),
+            events: VecDeque::new(),
+            timeout: IDLE_TIMEOUT,
+            sleep: Box::pin(sleep(IDLE_TIMEOUT)),
+        })
+    }
+
+    /// Re-issue `IDLE` every `timeout` instead of [IDLE_TIMEOUT]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self.sleep.as_mut().reset(Instant::now() + timeout);
+        self
+    }
+
+    /// Send `DONE` and wait for the completion of `IDLE`.
+    /// Updates which were not received from the stream are dropped
+    pub async fn done(mut self) -> Result<()> {
+        let inner = match std::mem::replace(&mut self.state, State::Finished) {
+            State::Idling(inner) => inner,
+            State::Restarting(restart) => restart.await?.0,
+            State::Finished => return Ok(()),
+        };
+
+        inner.finish(|_| {}).await.map(|_| ())
+    }
+}
+
+impl<S> Stream for Idle<'_, S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    type Item = Result<IdleEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(event) = this.events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+
+            match &mut this.state {
+                State::Idling(inner) => {
+                    if this.sleep.as_mut().poll(cx).is_ready() {
+                        if let State::Idling(inner) =
+                            std::mem::replace(&mut this.state, State::Finished)
+                        {
+                            this.state = State::Restarting(Box::pin(inner.restart()));
+                        }
+                        continue;
+                    }
+
+                    let line = match inner.responses.poll_next(cx) {
+                        Poll::Pending => return Poll::Pending,
+                        Poll::Ready(Ok(line)) => line,
+                        Poll::Ready(Err(e)) => {
+                            this.state = State::Finished;
+                            return Poll::Ready(Some(Err(e)));
+                        }
+                    };
+
+                    match receive(&line, inner.responses.tag()) {
+                        Received::Event(event) => return Poll::Ready(Some(Ok(event))),
+                        Received::Ignored => continue,
+                        Received::Completed(result) => {
+                            this.state = State::Finished;
+                            return Poll::Ready(result.err().map(Err));
+                        }
+                    }
+                }
+                State::Restarting(restart) => match restart.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Ok((inner, events))) => {
+                        this.events.extend(events);
+                        this.state = State::Idling(inner);
+                        this.sleep.as_mut().reset(Instant::now() + this.timeout);
+                    }
+                    Poll::Ready(Err(e)) => {
+                        this.state = State::Finished;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                },
+                State::Finished => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+struct Inner<'a, S> {
+    connection: &'a mut ImapConnection<S>,
+    responses: Responses,
+}
+
+impl<'a, S> Inner<'a, S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    /// Send `IDLE` and wait for the continuation request
+    async fn start(connection: &'a mut ImapConnection<S>) -> Result<Inner<'a, S>> {
+        let mut responses = connection.send(&Command::Idle).await?;
+        responses.continuation().await?;
+
+        Ok(Self {
+            connection,
+            responses,
+        })
+    }
+
+    /// Send `DONE` and wait for the completion, updates received
+    /// in between are passed to `handler`
+    async fn finish<F>(mut self, mut handler: F) -> Result<&'a mut ImapConnection<S>>
+    where
+        F: FnMut(IdleEvent),
+    {
+        self.connection.done().await?;
+
+        loop {
+            let line = self.responses.next().await?;
+            match receive(&line, self.responses.tag()) {
+                Received::Event(event) => handler(event),
+                Received::Ignored => {}
+                Received::Completed(result) => return result.map(|_| self.connection),
+            }
+        }
+    }
+
+    async fn restart(self) -> Result<(Inner<'a, S>, Vec<IdleEvent>)> {
+        let mut events = vec![];
+        let connection = self.finish(|event| events.push(event)).await?;
+        let inner = Inner::start(connection).await?;
+
+        Ok((inner, events))
+    }
+}
+
+// Meaning of the response for the idling client
+enum Received {
+    Event(IdleEvent),
+    Ignored,
+    // `IDLE` is completed or the connection is closed by the server
+    Completed(Result<()>),
+}
+
+fn receive(line: &[u8], tag: &Tag) -> Received {
+    let response = match parse_response(line) {
+        Ok((_, response)) => response,
+        Err(_) => {
+            warn!("Skip unknown response: {:?}", line);
+            return Received::Ignored;
+        }
+    };
+
+    match response {
+        ImapResponse::Data(UntaggedResponse::MailBox(data)) => {
+            Received::Event(IdleEvent::Mailbox((&data).into()))
+        }
+        ImapResponse::Data(UntaggedResponse::Message(data)) => {
+            Received::Event(IdleEvent::Message((&data).into()))
+        }
+        ImapResponse::Data(UntaggedResponse::Vanished(_, uids)) => {
+            Received::Event(IdleEvent::Vanished(uids))
+        }
+        ImapResponse::Data(UntaggedResponse::RespBye(bye)) => {
+            Received::Completed(Err(Error::Bye(bye.resp.text.to_owned())))
+        }
+        ImapResponse::Response(done) if done.tag == *tag => {
+            let text = done.resp.text.text.to_owned();
+            Received::Completed(match done.resp.status {
+                ImapResult::Ok => Ok(()),
+                ImapResult::No => Err(Error::No(text)),
+                ImapResult::Bad => Err(Error::Bad(text)),
+            })
+        }
+        // Keepalive `OK` and other responses are not interesting
+        _ => Received::Ignored,
+    }
+}
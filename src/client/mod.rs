@@ -1,5 +1,1022 @@
 //! Client api
 
+mod fetch;
+mod idle;
+
+pub use crate::command::{StatusAttribute, StoreMode};
+pub use fetch::FetchStream;
+pub use idle::{Idle, IdleEvent, IDLE_TIMEOUT};
+
+use crate::tls::{ChannelBinding, TlsConnect};
+use crate::{
+    command::Command,
+    error::{Error, Result},
+    fetch::{Fetch, FetchItem, FetchItems, Resync, Stored},
+    imapconnection::{ImapConnection, Responses},
+    parser::{
+        parse, parse_response,
+        types::{
+            ContinueReq, GreetingStatus, ImapResponse, ImapResult, MailBoxData, MessageData,
+            RespTextCode, TaggedResponse, UntaggedResponse,
+        },
+    },
+    sasl::Authenticator,
+    search::{SearchQuery, SearchResult},
+    types::{
+        self, Capabilities, Capability, CopyUid, DateTime, DefinedFlag, Expunged, Flag, Mailbox,
+        MailboxInfo, MailboxStatus, Moved, MsgAtt, RespText, SequenceSet,
+    },
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use log::warn;
+use std::collections::HashMap;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpStream, ToSocketAddrs},
+    sync::broadcast,
+};
+
 /// Client implementation
 #[derive(Debug)]
-pub struct Client {}
+pub struct Client<S = TcpStream> {
+    connection: ImapConnection<S>,
+    // Unknown until advertised by the server, dropped when they may change
+    capabilities: Option<Capabilities>,
+    // Extensions enabled with `ENABLE` in this session
+    enabled: Capabilities,
+    // Taken from the TLS session right after the handshake
+    channel_binding: Option<ChannelBinding>,
+}
+
+impl Client<TcpStream> {
+    /// Connect to the server and wait for the greeting.
+    /// Fails with [Error::Bye] if the server rejects the connection
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        let connection = ImapConnection::connect(addr).await?;
+        Self::with_connection(connection)
+    }
+
+    /// Connect to the server with implicit TLS (usually port 993)
+    /// and wait for the greeting
+    pub async fn connect_tls<A, C>(
+        addr: A,
+        domain: &str,
+        connector: &C,
+    ) -> Result<Client<C::Stream>>
+    where
+        A: ToSocketAddrs,
+        C: TlsConnect<TcpStream>,
+    {
+        let stream = TcpStream::connect(addr).await?;
+        let stream = connector.connect(domain, stream).await?;
+        let channel_binding = connector.channel_binding(&stream);
+
+        let mut client = Client::new(stream).await?;
+        client.channel_binding = channel_binding;
+        Ok(client)
+    }
+}
+
+impl<S> Client<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    /// Wait for the greeting on the already established stream
+    pub async fn new(stream: S) -> Result<Self> {
+        let connection = ImapConnection::new(stream).await?;
+        Self::with_connection(connection)
+    }
+
+    fn with_connection(connection: ImapConnection<S>) -> Result<Self> {
+        let greeting = connection.greeting();
+        let capabilities = match parse(greeting) {
+            Ok((_, ImapResponse::Greeting(greeting))) => match greeting.status {
+                GreetingStatus::Bye(bye) => return Err(Error::Bye(bye.resp.text.to_owned())),
+                GreetingStatus::Ok(text) | GreetingStatus::Preauth(text) => advertised(&text.code),
+            },
+            _ => return Err(Error::Parser(greeting.to_vec())),
+        };
+
+        Ok(Self {
+            connection,
+            capabilities,
+            enabled: Capabilities::default(),
+            channel_binding: None,
+        })
+    }
+
+    /// Upgrade the plaintext connection (usually port 143) to TLS
+    /// with `STARTTLS` command
+    pub async fn starttls<C>(mut self, domain: &str, connector: &C) -> Result<Client<C::Stream>>
+    where
+        C: TlsConnect<S>,
+    {
+        self.execute(Command::StartTls, |_| false).await?;
+
+        let mut channel_binding = None;
+        let connection = self
+            .connection
+            .upgrade(|stream| async {
+                let stream = connector.connect(domain, stream).await?;
+                channel_binding = connector.channel_binding(&stream);
+                Ok(stream)
+            })
+            .await?;
+
+        // Capabilities received before TLS can't be trusted
+        Ok(Client {
+            connection,
+            capabilities: None,
+            enabled: Capabilities::default(),
+            channel_binding,
+        })
+    }
+
+    /// Channel binding of the TLS session for
+    /// [Scram::with_channel_binding][crate::sasl::Scram::with_channel_binding],
+    /// `None` without TLS or if the [TlsConnect] implementation can't provide it
+    pub fn channel_binding(&self) -> Option<&ChannelBinding> {
+        self.channel_binding.as_ref()
+    }
+
+    /// Identify the client to the server with plaintext password
+    pub async fn login(&mut self, username: &str, password: &str) -> Result<()> {
+        let command = Command::Login { username, password };
+        let mut capabilities = None;
+
+        self.execute(command, |response| refresh(&mut capabilities, response))
+            .await?;
+
+        self.capabilities = capabilities;
+        Ok(())
+    }
+
+    /// Authenticate with SASL mechanism, see [crate::sasl] for the built-in
+    /// ones. The initial response is sent with the command if the server
+    /// advertises `SASL-IR`. Fails with [Error::Sasl] if the server completes
+    /// the command before the mechanism is finished, e.g. without proving
+    /// the SCRAM server signature
+    pub async fn authenticate<A: Authenticator>(&mut self, mut authenticator: A) -> Result<()> {
+        let mut initial_response = authenticator.initial_response();
+        let encoded = match initial_response {
+            Some(_) if self.capabilities().await?.contains(&Capability::SaslIr) => initial_response
+                .take()
+                .map(|response| match response.is_empty() {
+                    true => "=".to_owned(),
+                    false => BASE64.encode(response),
+                }),
+            _ => None,
+        };
+
+        let command = Command::Authenticate {
+            mechanism: authenticator.mechanism(),
+            initial_response: encoded.as_deref(),
+        };
+        let mut responses = self.connection.send(&command).await?;
+
+        while let Some(line) = responses.challenge().await? {
+            let challenge = match parse_response(&line) {
+                Ok((_, ImapResponse::Continue(ContinueReq::Base64(v)))) => {
+                    BASE64.decode(v).map_err(|_| Error::Parser(line.to_vec()))?
+                }
+                Ok((_, ImapResponse::Continue(ContinueReq::Text(_)))) => vec![],
+                _ => return Err(Error::Parser(line.to_vec())),
+            };
+
+            // Client-first mechanism gets an empty challenge without SASL-IR
+            let response = match initial_response.take() {
+                Some(response) => Ok(response),
+                None => authenticator.response(&challenge),
+            };
+
+            match response {
+                Ok(response) => {
+                    let response = BASE64.encode(response);
+                    self.connection.send_line(response.as_bytes()).await?
+                }
+                Err(e) => {
+                    // Cancel the exchange, the server answers with `BAD`
+                    self.connection.send_line(b"*").await?;
+                    let _ = complete(responses, |_| false).await;
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut capabilities = None;
+        complete(responses, |response| refresh(&mut capabilities, response)).await?;
+        authenticator.finish()?;
+
+        self.capabilities = capabilities;
+        Ok(())
+    }
+
+    /// Select a mailbox so that messages in the mailbox can be accessed
+    pub async fn select(&mut self, mailbox: &str) -> Result<Mailbox> {
+        self.select_or_examine(Command::Select(mailbox)).await
+    }
+
+    /// Select a mailbox in read-only mode
+    pub async fn examine(&mut self, mailbox: &str) -> Result<Mailbox> {
+        self.select_or_examine(Command::Examine(mailbox)).await
+    }
+
+    /// Select a mailbox and resynchronize the cache of it with QRESYNC.
+    /// `uid_validity` and `modseq` are the values known from the last
+    /// session, `known_uids` limits the messages reported as vanished.
+    /// `ENABLE QRESYNC` is sent first if needed, [Error::Unsupported] is
+    /// returned if the server does not enable it
+    pub async fn select_qresync(
+        &mut self,
+        mailbox: &str,
+        uid_validity: u32,
+        modseq: u64,
+        known_uids: Option<&SequenceSet>,
+    ) -> Result<Resync> {
+        if !self.enabled.contains(&Capability::QResync)
+            && !self
+                .enable(&[Capability::QResync])
+                .await?
+                .contains(&Capability::QResync)
+        {
+            return Err(Error::Unsupported("QRESYNC".to_owned()));
+        }
+
+        let command = Command::SelectQResync {
+            mailbox,
+            uid_validity,
+            modseq,
+            known_uids,
+        };
+        let mut resync = Resync::default();
+
+        self.execute(command, |response| match response {
+            ImapResponse::Data(UntaggedResponse::Vanished(_, uids)) => {
+                resync.vanished = std::mem::take(&mut resync.vanished).union(uids);
+                true
+            }
+            ImapResponse::Data(UntaggedResponse::Message(MessageData::Fetch(n, v))) => {
+                resync
+                    .changed
+                    .push(Fetch::new(*n, v.iter().map(MsgAtt::from).collect()));
+                true
+            }
+            response => resync.mailbox.apply(response),
+        })
+        .await?;
+
+        Ok(resync)
+    }
+
+    /// Create a mailbox
+    pub async fn create(&mut self, mailbox: &str) -> Result<()> {
+        self.execute(Command::Create(mailbox), |_| false).await
+    }
+
+    /// Permanently remove a mailbox
+    pub async fn delete(&mut self, mailbox: &str) -> Result<()> {
+        self.execute(Command::Delete(mailbox), |_| false).await
+    }
+
+    /// Change the name of a mailbox. Renaming `INBOX` moves its messages
+    /// to the new mailbox
+    pub async fn rename(&mut self, from: &str, to: &str) -> Result<()> {
+        self.execute(Command::Rename { from, to }, |_| false).await
+    }
+
+    /// Add a mailbox to the subscribed mailboxes returned by [Client::lsub]
+    pub async fn subscribe(&mut self, mailbox: &str) -> Result<()> {
+        self.execute(Command::Subscribe(mailbox), |_| false).await
+    }
+
+    /// Remove a mailbox from the subscribed mailboxes
+    pub async fn unsubscribe(&mut self, mailbox: &str) -> Result<()> {
+        self.execute(Command::Unsubscribe(mailbox), |_| false).await
+    }
+
+    /// List mailboxes matching `pattern` relative to `reference`.
+    /// `*` in the pattern matches any characters, `%` matches any
+    /// characters except the hierarchy delimiter
+    pub async fn list(&mut self, reference: &str, pattern: &str) -> Result<Vec<MailboxInfo>> {
+        self.list_mailboxes(Command::List { reference, pattern })
+            .await
+    }
+
+    /// List subscribed mailboxes, see [Client::list]
+    pub async fn lsub(&mut self, reference: &str, pattern: &str) -> Result<Vec<MailboxInfo>> {
+        self.list_mailboxes(Command::Lsub { reference, pattern })
+            .await
+    }
+
+    /// Request `items` of a mailbox without selecting it
+    pub async fn status(
+        &mut self,
+        mailbox: &str,
+        items: &[StatusAttribute],
+    ) -> Result<MailboxStatus> {
+        let mut status = MailboxStatus {
+            name: mailbox.to_owned(),
+            status: vec![],
+        };
+
+        self.execute(
+            Command::Status { mailbox, items },
+            |response| match response {
+                ImapResponse::Data(UntaggedResponse::MailBox(MailBoxData::Status(v))) => {
+                    status = v.into();
+                    true
+                }
+                _ => false,
+            },
+        )
+        .await?;
+
+        Ok(status)
+    }
+
+    /// Request capabilities that the server supports with `CAPABILITY`
+    /// command. The result is cached for [Client::capabilities]
+    pub async fn capability(&mut self) -> Result<&Capabilities> {
+        let mut capabilities = None;
+
+        self.execute(Command::Capability, |response| {
+            refresh(&mut capabilities, response)
+        })
+        .await?;
+
+        Ok(self.capabilities.insert(capabilities.unwrap_or_default()))
+    }
+
+    /// Capabilities that the server supports. They are taken from
+    /// the greeting or the completion of the authentication if advertised
+    /// there, otherwise requested with [Client::capability]
+    pub async fn capabilities(&mut self) -> Result<&Capabilities> {
+        match self.capabilities {
+            Some(ref capabilities) => Ok(capabilities),
+            None => self.capability().await,
+        }
+    }
+
+    /// Enable extensions with `ENABLE` (RFC 5161), it must be sent before
+    /// a mailbox is selected. Returns the extensions the server enabled,
+    /// unknown ones are ignored by the server
+    pub async fn enable(&mut self, capabilities: &[Capability]) -> Result<Capabilities> {
+        let mut enabled = Capabilities::default();
+
+        self.execute(Command::Enable(capabilities), |response| match response {
+            ImapResponse::Data(UntaggedResponse::Enabled(v)) => {
+                for capability in v.iter() {
+                    enabled.insert(capability.into())
+                }
+                true
+            }
+            _ => false,
+        })
+        .await?;
+
+        for capability in enabled.iter() {
+            self.enabled.insert(capability.clone())
+        }
+        Ok(enabled)
+    }
+
+    /// Extensions enabled with [Client::enable] in this session
+    pub fn enabled(&self) -> &Capabilities {
+        &self.enabled
+    }
+
+    /// Exchange identification with `ID` (RFC 2971). `params` are field
+    /// and value pairs describing the client, e.g. `("name", "rimap")`,
+    /// none are sent as `NIL`. Returns the fields sent by the server
+    pub async fn id(&mut self, params: &[(&str, &str)]) -> Result<HashMap<String, Option<String>>> {
+        let mut fields = HashMap::new();
+
+        self.execute(Command::Id(params), |response| match response {
+            ImapResponse::Data(data @ UntaggedResponse::Id(_)) => {
+                if let types::UntaggedResponse::Id(v) = types::UntaggedResponse::from(data) {
+                    fields = v
+                }
+                true
+            }
+            _ => false,
+        })
+        .await?;
+
+        Ok(fields)
+    }
+
+    /// Append the message to the end of `mailbox`. The message of `size`
+    /// octets is streamed from `body`, so it is not buffered in memory.
+    /// Returns the text of the completion to inspect its response codes
+    pub async fn append<R>(
+        &mut self,
+        mailbox: &str,
+        flags: &[Flag],
+        internal_date: Option<&DateTime>,
+        size: u32,
+        mut body: R,
+    ) -> Result<RespText>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        let command = Command::Append {
+            mailbox,
+            flags,
+            date: internal_date,
+            size,
+        };
+        let responses = self
+            .connection
+            .send_literal(&command, size, &mut body)
+            .await?;
+
+        let mut text = RespText::default();
+        complete(responses, |response| match response {
+            ImapResponse::Response(done) => {
+                text = (&done.resp.text).into();
+                true
+            }
+            _ => false,
+        })
+        .await?;
+
+        Ok(text)
+    }
+
+    /// Find messages in the selected mailbox matching `query`.
+    /// Returns their sequence numbers
+    pub async fn search(&mut self, query: &SearchQuery) -> Result<Vec<u32>> {
+        Ok(self.search_messages(query, false).await?.numbers)
+    }
+
+    /// Find messages in the selected mailbox matching `query`.
+    /// Returns their unique identifiers
+    pub async fn uid_search(&mut self, query: &SearchQuery) -> Result<Vec<u32>> {
+        Ok(self.search_messages(query, true).await?.numbers)
+    }
+
+    /// Find messages matching `query` with the [SearchQuery::ModSeq] key,
+    /// see [Client::search]. Returns their sequence numbers and the highest
+    /// mod-sequence among them. The server must support CONDSTORE
+    pub async fn search_modseq(&mut self, query: &SearchQuery) -> Result<SearchResult> {
+        self.search_messages(query, false).await
+    }
+
+    /// Find messages matching `query` with the [SearchQuery::ModSeq] key,
+    /// see [Client::search_modseq]. Returns their unique identifiers
+    pub async fn uid_search_modseq(&mut self, query: &SearchQuery) -> Result<SearchResult> {
+        self.search_messages(query, true).await
+    }
+
+    /// Fetch `items` of the messages with the sequence numbers in `set`.
+    /// Messages are yielded as soon as they arrive
+    pub async fn fetch(
+        &mut self,
+        set: &SequenceSet,
+        items: &FetchItems,
+    ) -> Result<FetchStream<'_, S>> {
+        self.fetch_messages(Command::Fetch {
+            set,
+            items,
+            changed_since: None,
+            uid: false,
+        })
+        .await
+    }
+
+    /// Fetch `items` of the messages with the unique identifiers in `set`.
+    /// `UID` is always included in the messages
+    pub async fn uid_fetch(
+        &mut self,
+        set: &SequenceSet,
+        items: &FetchItems,
+    ) -> Result<FetchStream<'_, S>> {
+        self.fetch_messages(Command::Fetch {
+            set,
+            items,
+            changed_since: None,
+            uid: true,
+        })
+        .await
+    }
+
+    /// Fetch `items` of the messages with the sequence numbers in `set`
+    /// whose mod-sequence is greater than `modseq`. The server must
+    /// support CONDSTORE, `MODSEQ` is always included in the messages
+    pub async fn fetch_changed_since(
+        &mut self,
+        set: &SequenceSet,
+        items: &FetchItems,
+        modseq: u64,
+    ) -> Result<FetchStream<'_, S>> {
+        self.fetch_messages(Command::Fetch {
+            set,
+            items,
+            changed_since: Some(modseq),
+            uid: false,
+        })
+        .await
+    }
+
+    /// Fetch `items` of the messages with the unique identifiers in `set`
+    /// whose mod-sequence is greater than `modseq`,
+    /// see [Client::fetch_changed_since]
+    pub async fn uid_fetch_changed_since(
+        &mut self,
+        set: &SequenceSet,
+        items: &FetchItems,
+        modseq: u64,
+    ) -> Result<FetchStream<'_, S>> {
+        self.fetch_messages(Command::Fetch {
+            set,
+            items,
+            changed_since: Some(modseq),
+            uid: true,
+        })
+        .await
+    }
+
+    /// Change the flags of the messages with the sequence numbers in `set`.
+    /// Returns the updated flags sent by the server in `FETCH` responses.
+    /// With `silent` the server does not send them, unless the flags were
+    /// changed by another client as well
+    pub async fn store(
+        &mut self,
+        set: &SequenceSet,
+        mode: StoreMode,
+        flags: &[Flag],
+        silent: bool,
+    ) -> Result<Vec<Fetch>> {
+        let command = Command::Store {
+            set,
+            unchanged_since: None,
+            mode,
+            silent,
+            flags,
+            uid: false,
+        };
+        Ok(self.store_flags(command).await?.messages)
+    }
+
+    /// Change the flags of the messages with the unique identifiers in `set`,
+    /// see [Client::store]
+    pub async fn uid_store(
+        &mut self,
+        set: &SequenceSet,
+        mode: StoreMode,
+        flags: &[Flag],
+        silent: bool,
+    ) -> Result<Vec<Fetch>> {
+        let command = Command::Store {
+            set,
+            unchanged_since: None,
+            mode,
+            silent,
+            flags,
+            uid: true,
+        };
+        Ok(self.store_flags(command).await?.messages)
+    }
+
+    /// Change the flags of the messages with the sequence numbers in `set`
+    /// unless their mod-sequence is greater than `modseq`, see [Client::store].
+    /// The server must support CONDSTORE
+    pub async fn store_unchanged_since(
+        &mut self,
+        set: &SequenceSet,
+        modseq: u64,
+        mode: StoreMode,
+        flags: &[Flag],
+        silent: bool,
+    ) -> Result<Stored> {
+        self.store_flags(Command::Store {
+            set,
+            unchanged_since: Some(modseq),
+            mode,
+            silent,
+            flags,
+            uid: false,
+        })
+        .await
+    }
+
+    /// Change the flags of the messages with the unique identifiers in `set`
+    /// unless their mod-sequence is greater than `modseq`,
+    /// see [Client::store_unchanged_since]
+    pub async fn uid_store_unchanged_since(
+        &mut self,
+        set: &SequenceSet,
+        modseq: u64,
+        mode: StoreMode,
+        flags: &[Flag],
+        silent: bool,
+    ) -> Result<Stored> {
+        self.store_flags(Command::Store {
+            set,
+            unchanged_since: Some(modseq),
+            mode,
+            silent,
+            flags,
+            uid: true,
+        })
+        .await
+    }
+
+    /// Copy the messages with the sequence numbers in `set`
+    /// to the end of `mailbox`. Returns the identifiers of the copies
+    /// if the server supports `UIDPLUS`
+    pub async fn copy(&mut self, set: &SequenceSet, mailbox: &str) -> Result<Option<CopyUid>> {
+        self.copy_messages(set, mailbox, false).await
+    }
+
+    /// Copy the messages with the unique identifiers in `set`
+    /// to the end of `mailbox`, see [Client::copy]
+    pub async fn uid_copy(&mut self, set: &SequenceSet, mailbox: &str) -> Result<Option<CopyUid>> {
+        self.copy_messages(set, mailbox, true).await
+    }
+
+    /// Move the messages with the sequence numbers in `set` to `mailbox`.
+    ///
+    /// Without `MOVE` extension the messages are copied, marked `\Deleted`
+    /// and expunged with `UID EXPUNGE`. If the server does not support
+    /// `UIDPLUS` either, [Error::Unsupported] is returned
+    pub async fn mv(&mut self, set: &SequenceSet, mailbox: &str) -> Result<Moved> {
+        self.move_messages(set, mailbox, false).await
+    }
+
+    /// Move the messages with the unique identifiers in `set` to `mailbox`,
+    /// see [Client::mv]
+    pub async fn uid_mv(&mut self, set: &SequenceSet, mailbox: &str) -> Result<Moved> {
+        self.move_messages(set, mailbox, true).await
+    }
+
+    /// Permanently remove all messages with the `\Deleted` flag from the
+    /// selected mailbox. Returns the sequence numbers of `EXPUNGE` responses
+    /// or the unique identifiers of `VANISHED` if QRESYNC is enabled
+    pub async fn expunge(&mut self) -> Result<Expunged> {
+        self.expunge_messages(Command::Expunge).await
+    }
+
+    /// Permanently remove the messages with the unique identifiers in `set`
+    /// and the `\Deleted` flag, see [Client::expunge]. The server must
+    /// support `UIDPLUS` extension
+    pub async fn uid_expunge(&mut self, set: &SequenceSet) -> Result<Expunged> {
+        self.expunge_messages(Command::UidExpunge(set)).await
+    }
+
+    /// Do nothing. The server sends pending mailbox updates with the
+    /// completion, they are delivered to [Client::unsolicited]
+    pub async fn noop(&mut self) -> Result<()> {
+        self.execute(Command::Noop, |_| false).await
+    }
+
+    /// Start `IDLE` to receive mailbox updates as soon as they happen.
+    /// The server must support `IDLE` extension
+    pub async fn idle(&mut self) -> Result<Idle<'_, S>> {
+        Idle::start(&mut self.connection).await
+    }
+
+    /// Subscribe to untagged responses which are not related to any command,
+    /// e.g. `EXISTS` or `EXPUNGE` sent between commands or along with
+    /// the responses of a command which does not consume them, and to `BYE`.
+    /// Only responses received after the call are delivered. A receiver
+    /// which falls behind gets [RecvError::Lagged] and skips the oldest ones
+    ///
+    /// [RecvError::Lagged]: tokio::sync::broadcast::error::RecvError::Lagged
+    pub fn unsolicited(&self) -> broadcast::Receiver<types::UntaggedResponse> {
+        self.connection.unsolicited()
+    }
+
+    /// Close the connection. The server sends `BYE` before the completion
+    pub async fn logout(mut self) -> Result<()> {
+        self.execute(Command::Logout, |_| false).await
+    }
+
+    async fn search_messages(&mut self, criteria: &SearchQuery, uid: bool) -> Result<SearchResult> {
+        let command = Command::Search {
+            charset: criteria.charset(),
+            criteria,
+            uid,
+        };
+        let mut result = SearchResult::default();
+
+        self.execute(command, |response| match response {
+            ImapResponse::Data(UntaggedResponse::MailBox(MailBoxData::Search(v, modseq))) => {
+                result.numbers.extend(v);
+                result.modseq = result.modseq.max(*modseq);
+                true
+            }
+            _ => false,
+        })
+        .await?;
+
+        Ok(result)
+    }
+
+    async fn fetch_messages(&mut self, command: Command<'_>) -> Result<FetchStream<'_, S>> {
+        let responses = self.connection.send(&command).await?;
+
+        Ok(FetchStream::new(&mut self.connection, responses))
+    }
+
+    async fn store_flags(&mut self, command: Command<'_>) -> Result<Stored> {
+        let mut stored = Stored::default();
+
+        self.execute(command, |response| match response {
+            ImapResponse::Data(UntaggedResponse::Message(MessageData::Fetch(n, v))) => {
+                stored
+                    .messages
+                    .push(Fetch::new(*n, v.iter().map(MsgAtt::from).collect()));
+                true
+            }
+            ImapResponse::Response(done) => {
+                for code in &done.resp.text.code {
+                    if let RespTextCode::Modified(v) = code {
+                        stored.modified = v.clone()
+                    }
+                }
+                true
+            }
+            _ => false,
+        })
+        .await?;
+
+        Ok(stored)
+    }
+
+    async fn copy_messages(
+        &mut self,
+        set: &SequenceSet,
+        mailbox: &str,
+        uid: bool,
+    ) -> Result<Option<CopyUid>> {
+        let mut copy_uid = None;
+
+        self.execute(
+            Command::Copy { set, mailbox, uid },
+            |response| match copied(response) {
+                Some(v) => {
+                    copy_uid = Some(v);
+                    true
+                }
+                None => false,
+            },
+        )
+        .await?;
+
+        Ok(copy_uid)
+    }
+
+    async fn move_messages(
+        &mut self,
+        set: &SequenceSet,
+        mailbox: &str,
+        uid: bool,
+    ) -> Result<Moved> {
+        let capabilities = self.capabilities().await?;
+        if capabilities.contains(&Capability::Move) {
+            let mut moved = Moved::default();
+
+            // `COPYUID` is sent in untagged `OK` before `EXPUNGE` responses
+            self.execute(
+                Command::Move { set, mailbox, uid },
+                |response| match response {
+                    ImapResponse::Data(UntaggedResponse::Message(MessageData::Expunge(n))) => {
+                        moved.expunged.push(*n);
+                        true
+                    }
+                    ImapResponse::Data(UntaggedResponse::Vanished(false, uids)) => {
+                        moved.vanished = std::mem::take(&mut moved.vanished).union(uids);
+                        true
+                    }
+                    response => match copied(response) {
+                        Some(v) => {
+                            moved.copy_uid = Some(v);
+                            true
+                        }
+                        None => false,
+                    },
+                },
+            )
+            .await?;
+
+            return Ok(moved);
+        }
+        // Plain `EXPUNGE` would remove other messages marked `\Deleted` too
+        if !capabilities.contains(&Capability::UidPlus) {
+            return Err(Error::Unsupported("MOVE or UIDPLUS".to_owned()));
+        }
+
+        // Sequence numbers change on expunge, so the messages are
+        // tracked by their unique identifiers
+        let uids = match uid {
+            true => set.clone(),
+            false => self.fetch_uids(set).await?,
+        };
+        if uids.is_empty() {
+            return Ok(Moved::default());
+        }
+
+        let copy_uid = self.uid_copy(&uids, mailbox).await?;
+        self.store_flags(Command::Store {
+            set: &uids,
+            unchanged_since: None,
+            mode: StoreMode::Add,
+            silent: true,
+            flags: &[Flag::Defined(DefinedFlag::Deleted)],
+            uid: true,
+        })
+        .await?;
+
+        let expunged = self.uid_expunge(&uids).await?;
+
+        Ok(Moved {
+            copy_uid,
+            expunged: expunged.messages,
+            vanished: expunged.vanished,
+        })
+    }
+
+    async fn fetch_uids(&mut self, set: &SequenceSet) -> Result<SequenceSet> {
+        let command = Command::Fetch {
+            set,
+            items: &FetchItem::Uid.into(),
+            changed_since: None,
+            uid: false,
+        };
+        let mut uids = SequenceSet::new();
+
+        self.execute(command, |response| match response {
+            ImapResponse::Data(UntaggedResponse::Message(MessageData::Fetch(n, v))) => {
+                if let Some(uid) = Fetch::new(*n, v.iter().map(MsgAtt::from).collect()).uid {
+                    uids.insert(uid)
+                }
+                true
+            }
+            _ => false,
+        })
+        .await?;
+
+        Ok(uids)
+    }
+
+    async fn expunge_messages(&mut self, command: Command<'_>) -> Result<Expunged> {
+        let mut expunged = Expunged::default();
+
+        self.execute(command, |response| match response {
+            ImapResponse::Data(UntaggedResponse::Message(MessageData::Expunge(n))) => {
+                expunged.messages.push(*n);
+                true
+            }
+            ImapResponse::Data(UntaggedResponse::Vanished(false, uids)) => {
+                expunged.vanished = std::mem::take(&mut expunged.vanished).union(uids);
+                true
+            }
+            _ => false,
+        })
+        .await?;
+
+        Ok(expunged)
+    }
+
+    async fn list_mailboxes(&mut self, command: Command<'_>) -> Result<Vec<MailboxInfo>> {
+        let mut mailboxes = vec![];
+
+        self.execute(command, |response| match response {
+            ImapResponse::Data(UntaggedResponse::MailBox(MailBoxData::List(v)))
+            | ImapResponse::Data(UntaggedResponse::MailBox(MailBoxData::Lsub(v))) => {
+                mailboxes.push(v.into());
+                true
+            }
+            _ => false,
+        })
+        .await?;
+
+        Ok(mailboxes)
+    }
+
+    async fn select_or_examine(&mut self, command: Command<'_>) -> Result<Mailbox> {
+        let mut result = Mailbox::default();
+
+        self.execute(command, |response| result.apply(response))
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Send the command and pass every received response including the tagged
+    /// completion to `handler`, it returns `false` for responses it does not
+    /// consume. `NO` and `BAD` completions are returned as errors
+    async fn execute<F>(&mut self, command: Command<'_>, handler: F) -> Result<()>
+    where
+        F: FnMut(&ImapResponse<'_>) -> bool,
+    {
+        let responses = self.connection.send(&command).await?;
+        complete(responses, handler).await
+    }
+}
+
+/// Capabilities in `CAPABILITY` response code
+fn advertised(code: &[RespTextCode<'_>]) -> Option<Capabilities> {
+    code.iter().find_map(|code| match code {
+        RespTextCode::Capability(v) => Some(v.iter().map(Capability::from).collect()),
+        _ => None,
+    })
+}
+
+/// `COPYUID` code of the untagged `OK` or the completion
+fn copied(response: &ImapResponse<'_>) -> Option<CopyUid> {
+    let text = match response {
+        ImapResponse::Data(UntaggedResponse::RespCond(cond)) => &cond.text,
+        ImapResponse::Response(done) => &done.resp.text,
+        _ => return None,
+    };
+
+    text.code.iter().find_map(|code| match code {
+        RespTextCode::CopyUid(uid_validity, source, destination) => Some(CopyUid {
+            uid_validity: *uid_validity,
+            source: source.clone(),
+            destination: destination.clone(),
+        }),
+        _ => None,
+    })
+}
+
+/// Take capabilities from untagged `CAPABILITY` or the response code
+/// of the completion
+fn refresh(capabilities: &mut Option<Capabilities>, response: &ImapResponse<'_>) -> bool {
+    let received = match response {
+        ImapResponse::Data(UntaggedResponse::Capability(v)) => {
+            Some(v.iter().map(Capability::from).collect())
+        }
+        ImapResponse::Data(UntaggedResponse::RespCond(cond)) => advertised(&cond.text.code),
+        ImapResponse::Response(done) => advertised(&done.resp.text.code),
+        _ => None,
+    };
+
+    match received {
+        Some(_) => {
+            *capabilities = received;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Pass responses to `handler` until the tagged completion of the command.
+/// Mailbox updates which are not consumed by `handler` are broadcast
+async fn complete<F>(mut responses: Responses, mut handler: F) -> Result<()>
+where
+    F: FnMut(&ImapResponse<'_>) -> bool,
+{
+    let tag = responses.tag().clone();
+    let prefix = format!("{} ", tag);
+    let mut bye = None;
+
+    loop {
+        let line = match responses.next().await {
+            Ok(line) => line,
+            Err(Error::ConnectionLost) if bye.is_some() => {
+                return Err(Error::Bye(bye.unwrap_or_default()))
+            }
+            Err(e) => return Err(e),
+        };
+
+        let response = match parse_response(&line) {
+            Ok((_, response)) => response,
+            Err(_) if line.starts_with(prefix.as_bytes()) => {
+                return Err(Error::Parser(line.to_vec()))
+            }
+            Err(_) => {
+                warn!("Skip unknown response: {:?}", line);
+                continue;
+            }
+        };
+
+        match response {
+            ImapResponse::Response(TaggedResponse {
+                tag: ref t,
+                ref resp,
+            }) if *t == tag => match resp.status {
+                ImapResult::Ok => {
+                    handler(&response);
+                    return Ok(());
+                }
+                ImapResult::No => return Err(Error::No(resp.text.text.to_owned())),
+                ImapResult::Bad => return Err(Error::Bad(resp.text.text.to_owned())),
+            },
+            ImapResponse::Data(UntaggedResponse::RespBye(ref v)) => {
+                bye = Some(v.resp.text.to_owned());
+                handler(&response);
+            }
+            ImapResponse::Data(ref data) => {
+                if !handler(&response) {
+                    responses.forward(data)
+                }
+            }
+            response => {
+                handler(&response);
+            }
+        }
+    }
+}
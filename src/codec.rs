@@ -0,0 +1,80 @@
+//! IMAP framing for the network stream
+
+use crate::error::{Error, Result};
+use crate::parser::response_frame;
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Splits the server output into complete responses.
+///
+/// A response is a line terminated by CRLF, but a line ending with a literal
+/// header `{n}` continues after `n` octets of literal data, so one response
+/// can contain any number of CRLFs. Frames are yielded as raw bytes including
+/// the trailing CRLF, ready to be passed to the parser.
+///
+/// Commands are already serialized, so encoding just copies bytes into the stream
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ImapCodec;
+
+impl Decoder for ImapCodec {
+    type Item = Bytes;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>> {
+        let length = match response_frame(src) {
+            Ok((_, frame)) => frame.len(),
+            Err(nom::Err::Incomplete(_)) => return Ok(None),
+            Err(_) => return Err(Error::Parser(src.to_vec())),
+        };
+
+        Ok(Some(src.split_to(length).freeze()))
+    }
+}
+
+impl Encoder<Bytes> for ImapCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: Bytes, dst: &mut BytesMut) -> Result<()> {
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImapCodec;
+    use bytes::BytesMut;
+    use tokio_util::codec::Decoder;
+
+    #[test]
+    fn decode_lines() {
+        let mut codec = ImapCodec;
+        let mut buf = BytesMut::from(&b"* 1 EXISTS\r\na1 OK do"[..]);
+
+        let frame = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(&frame[..], b"* 1 EXISTS\r\n");
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+
+        buf.extend_from_slice(b"ne\r\n");
+        let frame = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(&frame[..], b"a1 OK done\r\n");
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn decode_literals() {
+        let mut codec = ImapCodec;
+        let mut buf = BytesMut::from(&b"* 12 FETCH (BODY[HEADER] {12}\r\nSubject: "[..]);
+
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+
+        buf.extend_from_slice(b"a\r\n FLAGS (\\Seen) RFC822.TEXT {4}\r\n\r\n\r\n)\r\n* 13");
+
+        let frame = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            &frame[..],
+            &b"* 12 FETCH (BODY[HEADER] {12}\r\nSubject: a\r\n FLAGS (\\Seen) RFC822.TEXT {4}\r\n\r\n\r\n)\r\n"[..]
+        );
+        assert_eq!(&buf[..], b"* 13");
+    }
+}
@@ -0,0 +1,875 @@
+//! Commands sent to the server
+
+use crate::error::{Error, Result};
+use crate::fetch::FetchItems;
+use crate::parser::{is_astring_char, is_atom_char, is_list_char, is_quoted_char};
+use crate::search::SearchQuery;
+use crate::tag::Tag;
+use crate::types::{Capability, DateTime, Flag, SequenceSet};
+use bytes::Bytes;
+
+/// Item requested by `STATUS`
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum StatusAttribute {
+    /// Number of messages in the mailbox
+    Messages,
+    /// Number of messages with the `\Recent` flag set
+    Recent,
+    /// Next unique identifier value
+    UidNext,
+    /// Unique identifier validity value
+    UidValidity,
+    /// Number of messages without the `\Seen` flag set
+    Unseen,
+    /// Highest mod-sequence of all messages, RFC 7162
+    HighestModSeq,
+}
+
+impl StatusAttribute {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Messages => "MESSAGES",
+            Self::Recent => "RECENT",
+            Self::UidNext => "UIDNEXT",
+            Self::UidValidity => "UIDVALIDITY",
+            Self::Unseen => "UNSEEN",
+            Self::HighestModSeq => "HIGHESTMODSEQ",
+        }
+    }
+}
+
+/// How `STORE` changes the flags of the message
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum StoreMode {
+    /// `FLAGS`, replace the flags
+    Replace,
+    /// `+FLAGS`, add the flags
+    Add,
+    /// `-FLAGS`, remove the flags
+    Remove,
+}
+
+impl StoreMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Replace => "FLAGS",
+            Self::Add => "+FLAGS",
+            Self::Remove => "-FLAGS",
+        }
+    }
+}
+
+/// Command of RFC 3501 with its arguments
+#[derive(Debug, Clone)]
+pub(crate) enum Command<'a> {
+    // Any state
+    Capability,
+    Noop,
+    Logout,
+
+    // Not authenticated state
+    StartTls,
+    Authenticate {
+        mechanism: &'a str,
+        /// Base64 encoded initial response, `=` if it is empty
+        initial_response: Option<&'a str>,
+    },
+    Login {
+        username: &'a str,
+        password: &'a str,
+    },
+
+    // Authenticated state
+    Select(&'a str),
+    Examine(&'a str),
+    Create(&'a str),
+    Delete(&'a str),
+    Rename {
+        from: &'a str,
+        to: &'a str,
+    },
+    Subscribe(&'a str),
+    Unsubscribe(&'a str),
+    List {
+        reference: &'a str,
+        pattern: &'a str,
+    },
+    Lsub {
+        reference: &'a str,
+        pattern: &'a str,
+    },
+    Status {
+        mailbox: &'a str,
+        items: &'a [StatusAttribute],
+    },
+    // Only the literal header is serialized, the message is streamed
+    // by the connection
+    Append {
+        mailbox: &'a str,
+        flags: &'a [Flag],
+        date: Option<&'a DateTime>,
+        size: u32,
+    },
+
+    // Selected state
+    Check,
+    Close,
+    Expunge,
+    Search {
+        charset: Option<&'a str>,
+        criteria: &'a SearchQuery,
+        uid: bool,
+    },
+    Fetch {
+        set: &'a SequenceSet,
+        items: &'a FetchItems,
+        // CHANGEDSINCE modifier of RFC 7162
+        changed_since: Option<u64>,
+        uid: bool,
+    },
+    Store {
+        set: &'a SequenceSet,
+        // UNCHANGEDSINCE modifier of RFC 7162
+        unchanged_since: Option<u64>,
+        mode: StoreMode,
+        silent: bool,
+        flags: &'a [Flag],
+        uid: bool,
+    },
+    Copy {
+        set: &'a SequenceSet,
+        mailbox: &'a str,
+        uid: bool,
+    },
+
+    // Extensions
+    Idle,
+    /// RFC 6851
+    Move {
+        set: &'a SequenceSet,
+        mailbox: &'a str,
+        uid: bool,
+    },
+    /// `UID EXPUNGE` of RFC 4315
+    UidExpunge(&'a SequenceSet),
+    /// RFC 5161
+    Enable(&'a [Capability]),
+    /// `SELECT` with `QRESYNC` parameter of RFC 7162
+    SelectQResync {
+        mailbox: &'a str,
+        uid_validity: u32,
+        modseq: u64,
+        known_uids: Option<&'a SequenceSet>,
+    },
+    /// RFC 2971, field and value pairs of the client
+    Id(&'a [(&'a str, &'a str)]),
+}
+
+impl Command<'_> {
+    /// Serialize the command prefixed with `tag`.
+    /// See [Serializer::finish] for the parts
+    pub(crate) fn serialize(&self, tag: &Tag) -> Result<Vec<Bytes>> {
+        let mut s = Serializer::default();
+        s.atom(&tag.to_string()).sp();
+
+        match self {
+            Self::Capability => s.atom("CAPABILITY"),
+            Self::Noop => s.atom("NOOP"),
+            Self::Logout => s.atom("LOGOUT"),
+            Self::StartTls => s.atom("STARTTLS"),
+            Self::Authenticate {
+                mechanism,
+                initial_response,
+            } => {
+                s.atom("AUTHENTICATE").sp().checked_atom(mechanism);
+                match initial_response {
+                    Some(initial_response) => s.sp().atom(initial_response),
+                    None => &mut s,
+                }
+            }
+            Self::Login { username, password } => s
+                .atom("LOGIN")
+                .sp()
+                .astring(username.as_bytes())
+                .sp()
+                .astring(password.as_bytes()),
+            Self::Select(mailbox) => s.atom("SELECT").sp().astring(mailbox.as_bytes()),
+            Self::Examine(mailbox) => s.atom("EXAMINE").sp().astring(mailbox.as_bytes()),
+            Self::Create(mailbox) => s.atom("CREATE").sp().astring(mailbox.as_bytes()),
+            Self::Delete(mailbox) => s.atom("DELETE").sp().astring(mailbox.as_bytes()),
+            Self::Rename { from, to } => s
+                .atom("RENAME")
+                .sp()
+                .astring(from.as_bytes())
+                .sp()
+                .astring(to.as_bytes()),
+            Self::Subscribe(mailbox) => s.atom("SUBSCRIBE").sp().astring(mailbox.as_bytes()),
+            Self::Unsubscribe(mailbox) => s.atom("UNSUBSCRIBE").sp().astring(mailbox.as_bytes()),
+            Self::List { reference, pattern } => s
+                .atom("LIST")
+                .sp()
+                .astring(reference.as_bytes())
+                .sp()
+                .list_mailbox(pattern.as_bytes()),
+            Self::Lsub { reference, pattern } => s
+                .atom("LSUB")
+                .sp()
+                .astring(reference.as_bytes())
+                .sp()
+                .list_mailbox(pattern.as_bytes()),
+            Self::Status { mailbox, items } => s
+                .atom("STATUS")
+                .sp()
+                .astring(mailbox.as_bytes())
+                .sp()
+                .list(items.iter(), |s, item| s.atom(item.as_str())),
+            Self::Append {
+                mailbox,
+                flags,
+                date,
+                size,
+            } => {
+                s.atom("APPEND").sp().astring(mailbox.as_bytes()).sp();
+                if !flags.is_empty() {
+                    s.flags(flags).sp();
+                }
+                if let Some(date) = date {
+                    s.quoted(date.to_string().as_bytes()).sp();
+                }
+                s.literal_header(*size)
+            }
+            Self::Check => s.atom("CHECK"),
+            Self::Close => s.atom("CLOSE"),
+            Self::Expunge => s.atom("EXPUNGE"),
+            Self::Search {
+                charset,
+                criteria,
+                uid,
+            } => {
+                s.uid(*uid).atom("SEARCH").sp();
+                if let Some(charset) = charset {
+                    s.atom("CHARSET").sp().astring(charset.as_bytes()).sp();
+                }
+                criteria.serialize(&mut s)
+            }
+            Self::Fetch {
+                set,
+                items,
+                changed_since,
+                uid,
+            } => {
+                s.uid(*uid).atom("FETCH").sp().sequence_set(set).sp();
+                items.serialize(&mut s);
+                match changed_since {
+                    Some(v) => s.atom(&format!(" (CHANGEDSINCE {})", v)),
+                    None => &mut s,
+                }
+            }
+            Self::Store {
+                set,
+                unchanged_since,
+                mode,
+                silent,
+                flags,
+                uid,
+            } => {
+                s.uid(*uid).atom("STORE").sp().sequence_set(set).sp();
+                if let Some(v) = unchanged_since {
+                    s.atom(&format!("(UNCHANGEDSINCE {}) ", v));
+                }
+                s.atom(mode.as_str());
+                if *silent {
+                    s.atom(".SILENT");
+                }
+                s.sp().flags(flags)
+            }
+            Self::Copy { set, mailbox, uid } => s
+                .uid(*uid)
+                .atom("COPY")
+                .sp()
+                .sequence_set(set)
+                .sp()
+                .astring(mailbox.as_bytes()),
+            Self::Idle => s.atom("IDLE"),
+            Self::Move { set, mailbox, uid } => s
+                .uid(*uid)
+                .atom("MOVE")
+                .sp()
+                .sequence_set(set)
+                .sp()
+                .astring(mailbox.as_bytes()),
+            Self::UidExpunge(set) => s.uid(true).atom("EXPUNGE").sp().sequence_set(set),
+            Self::Enable(capabilities) => {
+                s.atom("ENABLE");
+                for capability in capabilities.iter() {
+                    s.sp().checked_atom(&capability.to_string());
+                }
+                &mut s
+            }
+            Self::SelectQResync {
+                mailbox,
+                uid_validity,
+                modseq,
+                known_uids,
+            } => {
+                s.atom("SELECT")
+                    .sp()
+                    .astring(mailbox.as_bytes())
+                    .sp()
+                    .atom(&format!("(QRESYNC ({} {}", uid_validity, modseq));
+                if let Some(uids) = known_uids {
+                    s.sp().sequence_set(uids);
+                }
+                s.atom("))")
+            }
+            // id-params-list = '(' #(string SP nstring) ')' | nil
+            Self::Id([]) => s.atom("ID NIL"),
+            Self::Id(params) => s.atom("ID ").list(params.iter(), |s, (field, value)| {
+                s.string(field.as_bytes()).sp().string(value.as_bytes())
+            }),
+        };
+
+        s.finish()
+    }
+}
+
+/// Writes arguments choosing between atom, quoted string and literal
+#[derive(Debug, Default)]
+pub(crate) struct Serializer {
+    parts: Vec<Bytes>,
+    buf: Vec<u8>,
+    // First argument given by the caller which is not valid
+    invalid: Option<String>,
+}
+
+impl Serializer {
+    /// Write `s` as is, the caller is responsible for the syntax
+    pub(crate) fn atom(&mut self, s: &str) -> &mut Self {
+        self.buf.extend_from_slice(s.as_bytes());
+        self
+    }
+
+    /// Write `s` given by the caller which must be an atom, e.g. a keyword.
+    /// Otherwise the command is not sent, see [Serializer::finish]
+    pub(crate) fn checked_atom(&mut self, s: &str) -> &mut Self {
+        if s.is_empty() || !s.bytes().all(is_atom_char) {
+            self.reject(s);
+        }
+        self.atom(s)
+    }
+
+    /// Record the argument `s` given by the caller which can't be sent,
+    /// see [Serializer::finish]. Nothing is written
+    pub(crate) fn reject(&mut self, s: &str) -> &mut Self {
+        self.invalid.get_or_insert_with(|| s.to_owned());
+        self
+    }
+
+    pub(crate) fn sp(&mut self) -> &mut Self {
+        self.buf.push(b' ');
+        self
+    }
+
+    fn uid(&mut self, uid: bool) -> &mut Self {
+        if uid {
+            self.atom("UID").sp();
+        }
+        self
+    }
+
+    // astring = 1*ASTRING-CHAR | string
+    pub(crate) fn astring(&mut self, s: &[u8]) -> &mut Self {
+        if !s.is_empty() && s.iter().all(|&c| is_astring_char(c)) {
+            self.buf.extend_from_slice(s);
+            self
+        } else {
+            self.string(s)
+        }
+    }
+
+    // list-mailbox = 1*list-char | string
+    pub(crate) fn list_mailbox(&mut self, s: &[u8]) -> &mut Self {
+        if !s.is_empty() && s.iter().all(|&c| is_list_char(c)) {
+            self.buf.extend_from_slice(s);
+            self
+        } else {
+            self.string(s)
+        }
+    }
+
+    // string = quoted | literal
+    pub(crate) fn string(&mut self, s: &[u8]) -> &mut Self {
+        if s.iter().all(|&c| is_quoted_char(c)) {
+            self.quoted(s)
+        } else {
+            self.literal(s)
+        }
+    }
+
+    // quoted = DQUOTE *QUOTED-CHAR DQUOTE
+    // `s` must contain only QUOTED-CHAR
+    pub(crate) fn quoted(&mut self, s: &[u8]) -> &mut Self {
+        self.buf.push(b'"');
+        for &c in s {
+            if c == b'"' || c == b'\\' {
+                self.buf.push(b'\\');
+            }
+            self.buf.push(c);
+        }
+        self.buf.push(b'"');
+        self
+    }
+
+    // literal = '{' number '}' CRLF *CHAR8
+    pub(crate) fn literal(&mut self, s: &[u8]) -> &mut Self {
+        self.literal_header(s.len() as u32);
+        self.buf.extend_from_slice(s);
+        self
+    }
+
+    /// Start a literal of `size` octets, the data is not written
+    pub(crate) fn literal_header(&mut self, size: u32) -> &mut Self {
+        self.buf
+            .extend_from_slice(format!("{{{}}}\r\n", size).as_bytes());
+        self.parts.push(std::mem::take(&mut self.buf).into());
+        self
+    }
+
+    // sequence-set, written compactly. Numbers start from 1, so neither
+    // an empty set nor zero can be sent
+    pub(crate) fn sequence_set(&mut self, set: &SequenceSet) -> &mut Self {
+        let s = set.to_string();
+        if set.is_empty() || set.contains(0) {
+            self.reject(&s);
+        }
+        self.atom(&s)
+    }
+
+    // flag-list = '(' [flag *(SP flag)] ')'
+    pub(crate) fn flags(&mut self, flags: &[Flag]) -> &mut Self {
+        self.list(flags.iter(), |s, flag| s.flag(flag))
+    }
+
+    // Keywords and extensions come from the caller, so they are checked
+    fn flag(&mut self, flag: &Flag) -> &mut Self {
+        match flag {
+            Flag::Keyword(v) => self.checked_atom(v),
+            // flag-extension = "\\" atom
+            Flag::Extension(v) => self
+                .atom("\\")
+                .checked_atom(v.strip_prefix('\\').unwrap_or(v)),
+            flag => self.atom(&flag.to_string()),
+        }
+    }
+
+    /// Parenthesized list of items separated by spaces
+    pub(crate) fn list<I, F>(&mut self, items: I, mut f: F) -> &mut Self
+    where
+        I: IntoIterator,
+        F: FnMut(&mut Self, I::Item) -> &mut Self,
+    {
+        self.buf.push(b'(');
+        for (i, item) in items.into_iter().enumerate() {
+            if i > 0 {
+                self.sp();
+            }
+            f(self, item);
+        }
+        self.buf.push(b')');
+        self
+    }
+
+    /// Terminate the command with CRLF. Every part except the last ends with
+    /// a synchronizing literal header `{n}\r\n`, the next part must be sent
+    /// only after the continuation request from the server.
+    /// Fails with [Error::InvalidArgument] if an argument can't be sent
+    pub(crate) fn finish(mut self) -> Result<Vec<Bytes>> {
+        if let Some(invalid) = self.invalid {
+            return Err(Error::InvalidArgument(invalid));
+        }
+
+        self.buf.extend_from_slice(b"\r\n");
+        self.parts.push(self.buf.into());
+        Ok(self.parts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, StatusAttribute, StoreMode};
+    use crate::error::Error;
+    use crate::fetch::{FetchItem, FetchItems};
+    use crate::search::{Date, SearchQuery};
+    use crate::tag::Tag;
+    use crate::types::{
+        Capability, DateTime, DefinedFlag, Flag, Month, Section, SectionText, SequenceSet, Time,
+    };
+
+    fn serialize(command: Command<'_>) -> Vec<String> {
+        command
+            .serialize(&Tag::new('a', 1))
+            .unwrap()
+            .iter()
+            .map(|part| String::from_utf8(part.to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn serialize_astring() {
+        let login = |username, password| Command::Login { username, password };
+
+        assert_eq!(serialize(login("user", "pass")), ["a1 LOGIN user pass\r\n"]);
+        assert_eq!(
+            serialize(login("user name", "pa\"ss\\")),
+            ["a1 LOGIN \"user name\" \"pa\\\"ss\\\\\"\r\n"]
+        );
+        assert_eq!(
+            serialize(login("", "pässword")),
+            ["a1 LOGIN \"\" {9}\r\n", "pässword\r\n"]
+        );
+        assert_eq!(
+            serialize(login("line\r\n", "")),
+            ["a1 LOGIN {6}\r\n", "line\r\n \"\"\r\n"]
+        );
+    }
+
+    #[test]
+    fn serialize_mailbox_commands() {
+        assert_eq!(
+            serialize(Command::List {
+                reference: "",
+                pattern: "INBOX.%",
+            }),
+            ["a1 LIST \"\" INBOX.%\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::Rename {
+                from: "Old [Box]",
+                to: "New",
+            }),
+            ["a1 RENAME \"Old [Box]\" New\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::Status {
+                mailbox: "INBOX",
+                items: &[StatusAttribute::Messages, StatusAttribute::UidNext],
+            }),
+            ["a1 STATUS INBOX (MESSAGES UIDNEXT)\r\n"]
+        );
+    }
+
+    #[test]
+    fn serialize_append() {
+        let date = DateTime {
+            day: 7,
+            month: Month::Feb,
+            year: 1994,
+            time: Time {
+                hours: 21,
+                minutes: 52,
+                seconds: 25,
+            },
+            zone: -800,
+        };
+
+        assert_eq!(
+            serialize(Command::Append {
+                mailbox: "saved-messages",
+                flags: &[Flag::Defined(DefinedFlag::Seen)],
+                date: Some(&date),
+                size: 22,
+            }),
+            [
+                "a1 APPEND saved-messages (\\Seen) \" 7-Feb-1994 21:52:25 -0800\" {22}\r\n",
+                "\r\n"
+            ]
+        );
+        assert_eq!(
+            serialize(Command::Append {
+                mailbox: "Drafts",
+                flags: &[],
+                date: None,
+                size: 0,
+            }),
+            ["a1 APPEND Drafts {0}\r\n", "\r\n"]
+        );
+    }
+
+    #[test]
+    fn serialize_selected_commands() {
+        assert_eq!(
+            serialize(Command::Store {
+                set: &SequenceSet::range(2, 4),
+                unchanged_since: None,
+                mode: StoreMode::Add,
+                silent: true,
+                flags: &[
+                    Flag::Defined(DefinedFlag::Deleted),
+                    Flag::Keyword("$Junk".to_owned()),
+                ],
+                uid: false,
+            }),
+            ["a1 STORE 2:4 +FLAGS.SILENT (\\Deleted $Junk)\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::Search {
+                charset: Some("UTF-8"),
+                criteria: &SearchQuery::Unseen,
+                uid: true,
+            }),
+            ["a1 UID SEARCH CHARSET UTF-8 UNSEEN\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::Copy {
+                set: &SequenceSet::all(),
+                mailbox: "Archive 2021",
+                uid: true,
+            }),
+            ["a1 UID COPY 1:* \"Archive 2021\"\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::Move {
+                set: &SequenceSet::single(5),
+                mailbox: "Trash",
+                uid: false,
+            }),
+            ["a1 MOVE 5 Trash\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::UidExpunge(&SequenceSet::range(3000, 3002))),
+            ["a1 UID EXPUNGE 3000:3002\r\n"]
+        );
+    }
+
+    #[test]
+    fn serialize_condstore() {
+        assert_eq!(
+            serialize(Command::Fetch {
+                set: &SequenceSet::all(),
+                items: &vec![FetchItem::Flags, FetchItem::ModSeq].into(),
+                changed_since: Some(12345),
+                uid: false,
+            }),
+            ["a1 FETCH 1:* (FLAGS MODSEQ) (CHANGEDSINCE 12345)\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::Store {
+                set: &"7,5,9".parse().unwrap(),
+                unchanged_since: Some(320162338),
+                mode: StoreMode::Add,
+                silent: false,
+                flags: &[Flag::Defined(DefinedFlag::Deleted)],
+                uid: true,
+            }),
+            ["a1 UID STORE 5,7,9 (UNCHANGEDSINCE 320162338) +FLAGS (\\Deleted)\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::Search {
+                charset: None,
+                criteria: &SearchQuery::ModSeq(620162338),
+                uid: false,
+            }),
+            ["a1 SEARCH MODSEQ 620162338\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::Status {
+                mailbox: "INBOX",
+                items: &[StatusAttribute::HighestModSeq],
+            }),
+            ["a1 STATUS INBOX (HIGHESTMODSEQ)\r\n"]
+        );
+    }
+
+    #[test]
+    fn serialize_qresync() {
+        assert_eq!(
+            serialize(Command::Enable(&[Capability::QResync])),
+            ["a1 ENABLE QRESYNC\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::SelectQResync {
+                mailbox: "INBOX",
+                uid_validity: 67890007,
+                modseq: 20050715194045000,
+                known_uids: Some(&"41,43:211,214:541".parse().unwrap()),
+            }),
+            ["a1 SELECT INBOX (QRESYNC (67890007 20050715194045000 41,43:211,214:541))\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::SelectQResync {
+                mailbox: "INBOX",
+                uid_validity: 67890007,
+                modseq: 90060115194045000,
+                known_uids: None,
+            }),
+            ["a1 SELECT INBOX (QRESYNC (67890007 90060115194045000))\r\n"]
+        );
+    }
+
+    #[test]
+    fn serialize_extensions() {
+        assert_eq!(
+            serialize(Command::Enable(&[
+                Capability::CondStore,
+                Capability::Other("X-GOOD-IDEA".to_owned())
+            ])),
+            ["a1 ENABLE CONDSTORE X-GOOD-IDEA\r\n"]
+        );
+        assert_eq!(
+            serialize(Command::Id(&[("name", "rimap"), ("version", "0.1")])),
+            ["a1 ID (\"name\" \"rimap\" \"version\" \"0.1\")\r\n"]
+        );
+        assert_eq!(serialize(Command::Id(&[])), ["a1 ID NIL\r\n"]);
+    }
+
+    #[test]
+    fn reject_invalid_arguments() {
+        let tag = Tag::new('a', 1);
+        let set = SequenceSet::single(1);
+        let store = |flags: &[Flag]| {
+            Command::Store {
+                set: &set,
+                unchanged_since: None,
+                mode: StoreMode::Add,
+                silent: false,
+                flags,
+                uid: false,
+            }
+            .serialize(&tag)
+        };
+
+        assert!(matches!(
+            store(&[Flag::Keyword("x)\r\na9 DELETE INBOX".to_owned())]),
+            Err(Error::InvalidArgument(v)) if v == "x)\r\na9 DELETE INBOX"
+        ));
+        assert!(store(&[Flag::Keyword(String::new())]).is_err());
+        assert!(store(&[Flag::Extension("\\Bad Flag".to_owned())]).is_err());
+
+        let parts = store(&[
+            Flag::Keyword("$Junk".to_owned()),
+            Flag::Extension("\\Custom".to_owned()),
+        ])
+        .unwrap();
+        assert_eq!(parts, ["a1 STORE 1 +FLAGS ($Junk \\Custom)\r\n"]);
+
+        let search = |criteria: &SearchQuery| {
+            Command::Search {
+                charset: None,
+                criteria,
+                uid: false,
+            }
+            .serialize(&tag)
+        };
+        assert!(search(&SearchQuery::Keyword("a b".to_owned())).is_err());
+        // Empty AND would be sent as `()`
+        assert!(search(&SearchQuery::And(vec![])).is_err());
+        assert!(search(&SearchQuery::Seen.or(SearchQuery::And(vec![]))).is_err());
+
+        let capabilities = [Capability::Other("X (".to_owned())];
+        assert!(Command::Enable(&capabilities).serialize(&tag).is_err());
+
+        let fetch = |set: &SequenceSet| {
+            Command::Fetch {
+                set,
+                items: &FetchItems::All,
+                changed_since: None,
+                uid: true,
+            }
+            .serialize(&tag)
+        };
+        let items = FetchItems::Items(vec![]);
+        assert!(Command::Fetch {
+            set: &set,
+            items: &items,
+            changed_since: None,
+            uid: false,
+        }
+        .serialize(&tag)
+        .is_err());
+        assert!(matches!(
+            fetch(&SequenceSet::new()),
+            Err(Error::InvalidArgument(v)) if v.is_empty()
+        ));
+        assert!(matches!(
+            fetch(&SequenceSet::single(0)),
+            Err(Error::InvalidArgument(v)) if v == "0"
+        ));
+        assert!(fetch(&SequenceSet::range(0, 3)).is_err());
+    }
+
+    #[test]
+    fn serialize_search() {
+        let search = |criteria: &SearchQuery| {
+            serialize(Command::Search {
+                charset: None,
+                criteria,
+                uid: false,
+            })
+        };
+
+        assert_eq!(
+            search(
+                &SearchQuery::SequenceSet(SequenceSet::all())
+                    .and(SearchQuery::Since(Date::new(1994, Month::Feb, 1)))
+                    .and(SearchQuery::Flagged.or(SearchQuery::Larger(1024)))
+                    .and(SearchQuery::Seen.not())
+            ),
+            ["a1 SEARCH 1:* SINCE 1-Feb-1994 OR FLAGGED LARGER 1024 NOT SEEN\r\n"]
+        );
+        assert_eq!(
+            search(
+                &SearchQuery::Deleted
+                    .and(SearchQuery::From("Smith".to_owned()))
+                    .or(SearchQuery::Header("X-Id".to_owned(), "".to_owned()))
+            ),
+            ["a1 SEARCH OR (DELETED FROM Smith) HEADER X-Id \"\"\r\n"]
+        );
+        assert_eq!(
+            search(&SearchQuery::Subject("Привет".to_owned())),
+            ["a1 SEARCH SUBJECT {12}\r\n", "Привет\r\n"]
+        );
+    }
+
+    #[test]
+    fn serialize_fetch() {
+        let fetch = |items: &FetchItems| {
+            serialize(Command::Fetch {
+                set: &SequenceSet::range(1, 3),
+                items,
+                changed_since: None,
+                uid: true,
+            })
+        };
+
+        assert_eq!(fetch(&FetchItems::All), ["a1 UID FETCH 1:3 ALL\r\n"]);
+        assert_eq!(
+            fetch(&FetchItem::body_section(Section::default()).into()),
+            ["a1 UID FETCH 1:3 BODY[]\r\n"]
+        );
+        assert_eq!(
+            fetch(&FetchItems::Fast.with(FetchItem::Uid)),
+            ["a1 UID FETCH 1:3 (FLAGS INTERNALDATE RFC822.SIZE UID)\r\n"]
+        );
+        assert_eq!(
+            fetch(
+                &vec![
+                    FetchItem::Flags,
+                    FetchItem::body_peek(Section {
+                        part: vec![1, 2],
+                        text: Some(SectionText::HeaderFields(vec![
+                            "From".to_owned(),
+                            "Subject".to_owned()
+                        ])),
+                    })
+                    .partial(0, 100),
+                    FetchItem::body_section(Section {
+                        part: vec![],
+                        text: Some(SectionText::Text),
+                    }),
+                ]
+                .into()
+            ),
+            ["a1 UID FETCH 1:3 (FLAGS BODY.PEEK[1.2.HEADER.FIELDS (From Subject)]<0.100> BODY[TEXT])\r\n"]
+        );
+    }
+}
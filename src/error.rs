@@ -17,12 +17,35 @@ pub enum Error {
     Custom(String),
     #[error("Parser error [slice: {0:?}, str: {}]", vec_to_string(.0))]
     Parser(Vec<u8>),
+    /// Server answered to the command with `NO`
+    #[error("Command failed: {0}")]
+    No(String),
+    /// Server answered to the command with `BAD`
+    #[error("Command rejected: {0}")]
+    Bad(String),
+    /// Server closed the connection with `BYE`
+    #[error("Connection closed by server: {0}")]
+    Bye(String),
+    /// SASL mechanism failed to process the challenge
+    #[error("Authentication failed: {0}")]
+    Sasl(String),
+    /// Background reader stopped, no more responses will be received
+    #[error("Connection lost")]
+    ConnectionLost,
+    /// Argument can't be sent in the command, e.g. a keyword with spaces.
+    /// Nothing is sent to the server
+    #[error("Invalid argument: {0:?}")]
+    InvalidArgument(String),
+    /// Server does not advertise the extension the command relies on.
+    /// Nothing is sent to the server
+    #[error("Extension is not supported: {0}")]
+    Unsupported(String),
 }
 
 pub fn create_custom_error(msg: String) -> Error {
     Error::Custom(msg)
 }
 
-fn vec_to_string(v: &Vec<u8>) -> String {
-    std::string::String::from_utf8_lossy(&v[..]).into_owned()
+fn vec_to_string(v: &[u8]) -> String {
+    std::string::String::from_utf8_lossy(v).into_owned()
 }
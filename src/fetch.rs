@@ -0,0 +1,298 @@
+//! `FETCH` data items and the fetched message
+
+use crate::command::Serializer;
+use crate::types::{
+    Body, DateTime, Envelope, Flag, Mailbox, MsgAtt, Section, SectionText, SequenceSet,
+};
+
+/// Data item requested by `FETCH`
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum FetchItem {
+    /// `ENVELOPE`
+    Envelope,
+    /// `FLAGS`
+    Flags,
+    /// `INTERNALDATE`
+    InternalDate,
+    /// `RFC822`, the whole message, sets `\Seen` flag
+    Rfc822,
+    /// `RFC822.HEADER`
+    Rfc822Header,
+    /// `RFC822.SIZE`
+    Rfc822Size,
+    /// `RFC822.TEXT`, sets `\Seen` flag
+    Rfc822Text,
+    /// `BODY`, structure without extension data
+    Body,
+    /// `BODYSTRUCTURE`
+    BodyStructure,
+    /// `UID`
+    Uid,
+    /// `MODSEQ` of CONDSTORE
+    ModSeq,
+    /// `BODY[<section>]<<origin>.<size>>`, `BODY.PEEK` does not set
+    /// `\Seen` flag
+    BodySection {
+        section: Section,
+        peek: bool,
+        /// First octet and the maximum number of octets
+        partial: Option<(u32, u32)>,
+    },
+}
+
+impl FetchItem {
+    /// `BODY[<section>]`
+    pub fn body_section(section: Section) -> Self {
+        Self::BodySection {
+            section,
+            peek: false,
+            partial: None,
+        }
+    }
+
+    /// `BODY.PEEK[<section>]`
+    pub fn body_peek(section: Section) -> Self {
+        Self::BodySection {
+            section,
+            peek: true,
+            partial: None,
+        }
+    }
+
+    /// Request `size` octets starting from `origin` of the body section.
+    /// Other items are not changed
+    pub fn partial(self, origin: u32, size: u32) -> Self {
+        match self {
+            Self::BodySection { section, peek, .. } => Self::BodySection {
+                section,
+                peek,
+                partial: Some((origin, size)),
+            },
+            item => item,
+        }
+    }
+
+    fn serialize<'s>(&self, s: &'s mut Serializer) -> &'s mut Serializer {
+        match self {
+            Self::Envelope => s.atom("ENVELOPE"),
+            Self::Flags => s.atom("FLAGS"),
+            Self::InternalDate => s.atom("INTERNALDATE"),
+            Self::Rfc822 => s.atom("RFC822"),
+            Self::Rfc822Header => s.atom("RFC822.HEADER"),
+            Self::Rfc822Size => s.atom("RFC822.SIZE"),
+            Self::Rfc822Text => s.atom("RFC822.TEXT"),
+            Self::Body => s.atom("BODY"),
+            Self::BodyStructure => s.atom("BODYSTRUCTURE"),
+            Self::Uid => s.atom("UID"),
+            Self::ModSeq => s.atom("MODSEQ"),
+            Self::BodySection {
+                section,
+                peek,
+                partial,
+            } => {
+                s.atom(if *peek { "BODY.PEEK[" } else { "BODY[" });
+                serialize_section(s, section).atom("]");
+                match partial {
+                    Some((origin, size)) => s.atom(&format!("<{}.{}>", origin, size)),
+                    None => s,
+                }
+            }
+        }
+    }
+}
+
+// section-spec = section-msgtext | (section-part ['.' section-text])
+fn serialize_section<'s>(s: &'s mut Serializer, section: &Section) -> &'s mut Serializer {
+    let part = section
+        .part
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(".");
+    s.atom(&part);
+
+    let text = match &section.text {
+        Some(text) => text,
+        None => return s,
+    };
+    if !part.is_empty() {
+        s.atom(".");
+    }
+
+    match text {
+        SectionText::Header => s.atom("HEADER"),
+        SectionText::HeaderFields(fields) => s
+            .atom("HEADER.FIELDS ")
+            .list(fields, |s, field| s.astring(field.as_bytes())),
+        SectionText::HeaderFieldsNot(fields) => s
+            .atom("HEADER.FIELDS.NOT ")
+            .list(fields, |s, field| s.astring(field.as_bytes())),
+        SectionText::Text => s.atom("TEXT"),
+        SectionText::Mime => s.atom("MIME"),
+    }
+}
+
+/// Data items of `FETCH`, either a macro or a list of items
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum FetchItems {
+    /// `ALL`, same as `(FLAGS INTERNALDATE RFC822.SIZE ENVELOPE)`
+    All,
+    /// `FAST`, same as `(FLAGS INTERNALDATE RFC822.SIZE)`
+    Fast,
+    /// `FULL`, same as `(FLAGS INTERNALDATE RFC822.SIZE ENVELOPE BODY)`
+    Full,
+    /// List of items, empty list can't be sent
+    Items(Vec<FetchItem>),
+}
+
+impl FetchItems {
+    /// Add `item` to the list, a macro is expanded first
+    pub fn with(self, item: FetchItem) -> Self {
+        let mut items = match self {
+            Self::All => vec![
+                FetchItem::Flags,
+                FetchItem::InternalDate,
+                FetchItem::Rfc822Size,
+                FetchItem::Envelope,
+            ],
+            Self::Fast => vec![
+                FetchItem::Flags,
+                FetchItem::InternalDate,
+                FetchItem::Rfc822Size,
+            ],
+            Self::Full => vec![
+                FetchItem::Flags,
+                FetchItem::InternalDate,
+                FetchItem::Rfc822Size,
+                FetchItem::Envelope,
+                FetchItem::Body,
+            ],
+            Self::Items(items) => items,
+        };
+        items.push(item);
+        Self::Items(items)
+    }
+
+    pub(crate) fn serialize<'s>(&self, s: &'s mut Serializer) -> &'s mut Serializer {
+        match self {
+            Self::All => s.atom("ALL"),
+            Self::Fast => s.atom("FAST"),
+            Self::Full => s.atom("FULL"),
+            Self::Items(items) if items.is_empty() => s.reject("()"),
+            Self::Items(items) if items.len() == 1 => items[0].serialize(s),
+            Self::Items(items) => s.list(items, |s, item| item.serialize(s)),
+        }
+    }
+}
+
+impl From<FetchItem> for FetchItems {
+    fn from(item: FetchItem) -> Self {
+        Self::Items(vec![item])
+    }
+}
+
+impl From<Vec<FetchItem>> for FetchItems {
+    fn from(items: Vec<FetchItem>) -> Self {
+        Self::Items(items)
+    }
+}
+
+/// Content of the body section
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BodySection {
+    pub section: Section,
+    /// First octet of the partial data
+    pub origin: Option<u32>,
+    /// `None` if the section is `NIL`
+    pub data: Option<Vec<u8>>,
+}
+
+/// Message data received from `FETCH`. Items which were not
+/// requested are not set
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Fetch {
+    /// Message sequence number
+    pub message: u32,
+    pub uid: Option<u32>,
+    pub flags: Option<Vec<Flag>>,
+    pub envelope: Option<Envelope>,
+    pub internal_date: Option<DateTime>,
+    /// `RFC822.SIZE`
+    pub size: Option<u32>,
+    /// `BODY`, structure without extension data
+    pub body: Option<Body>,
+    pub body_structure: Option<Body>,
+    pub rfc822: Option<Vec<u8>>,
+    pub rfc822_header: Option<Vec<u8>>,
+    pub rfc822_text: Option<Vec<u8>>,
+    pub sections: Vec<BodySection>,
+    /// Mod-sequence of CONDSTORE
+    pub modseq: Option<u64>,
+}
+
+impl Fetch {
+    /// Content of the body section, `None` if it was not received or `NIL`
+    pub fn section(&self, section: &Section) -> Option<&[u8]> {
+        self.sections
+            .iter()
+            .find(|v| v.section == *section)
+            .and_then(|v| v.data.as_deref())
+    }
+
+    pub(crate) fn new(message: u32, attributes: Vec<MsgAtt>) -> Self {
+        let mut fetch = Self {
+            message,
+            ..Self::default()
+        };
+
+        for attribute in attributes {
+            match attribute {
+                MsgAtt::Envelope(v) => fetch.envelope = Some(v),
+                MsgAtt::InternalDate(v) => fetch.internal_date = Some(v),
+                MsgAtt::Rfc822(v) => fetch.rfc822 = v,
+                MsgAtt::Rfc822Header(v) => fetch.rfc822_header = v,
+                MsgAtt::Rfc822Text(v) => fetch.rfc822_text = v,
+                MsgAtt::Rfc822Size(v) => fetch.size = Some(v),
+                MsgAtt::Body(v) => fetch.body = Some(v),
+                MsgAtt::BodyStructure(v) => fetch.body_structure = Some(v),
+                MsgAtt::BodySection {
+                    section,
+                    origin,
+                    data,
+                } => fetch.sections.push(BodySection {
+                    section,
+                    origin,
+                    data,
+                }),
+                MsgAtt::Uid(v) => fetch.uid = Some(v),
+                MsgAtt::Flags(v) => fetch.flags = Some(v),
+                MsgAtt::ModSeq(v) => fetch.modseq = Some(v),
+            }
+        }
+
+        fetch
+    }
+}
+
+/// Result of `STORE` with `UNCHANGEDSINCE` of CONDSTORE
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Stored {
+    /// Updated flags and mod-sequences of the messages
+    pub messages: Vec<Fetch>,
+    /// Messages which were not updated because they were modified
+    /// after the mod-sequence, `MODIFIED` code
+    pub modified: SequenceSet,
+}
+
+/// Result of `SELECT` with `QRESYNC`. If the unique identifier validity
+/// has changed the server ignores the parameter, so [Resync::vanished]
+/// and [Resync::changed] are empty and the cache must be dropped
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Resync {
+    pub mailbox: Mailbox,
+    /// Messages expunged since the mod-sequence, `VANISHED (EARLIER)`
+    pub vanished: SequenceSet,
+    /// Messages changed since the mod-sequence with their unique
+    /// identifiers, flags and mod-sequences
+    pub changed: Vec<Fetch>,
+}
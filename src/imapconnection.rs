@@ -1,19 +1,43 @@
-//!
-use crate::error::Result;
+//! Network connection to the IMAP server
+use crate::codec::ImapCodec;
+use crate::command::Command;
+use crate::error::{create_custom_error, Error, Result};
+use crate::parser::{
+    parse_response, response_tag,
+    types::{self as parser, ImapResponse, ImapResult, TaggedResponse},
+};
 use crate::tag::{Tag, TagGenerator};
-use futures::stream::SplitSink;
-use futures::{SinkExt, StreamExt};
+use crate::types::UntaggedResponse;
+use bytes::{Bytes, BytesMut};
+use futures::stream::{SplitSink, SplitStream};
+use futures::{Future, SinkExt, StreamExt};
 use log::trace;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::mpsc::Sender;
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use tokio::sync::{
+    broadcast,
+    mpsc::{channel, Receiver, Sender},
+    oneshot,
+};
 use tokio::{
     net::{TcpStream, ToSocketAddrs},
     task::JoinHandle,
 };
-use tokio_util::codec::{Decoder, Framed, LinesCodec};
+use tokio_util::codec::{Decoder, Framed};
+
+// Number of responses buffered for one command
+const RESPONSES_BUFFER: usize = 32;
+
+// Number of unsolicited responses kept for slow listeners
+const UNSOLICITED_BUFFER: usize = 64;
 
-/// An async tcp stream.
+// Size of one chunk of the streamed literal
+const LITERAL_CHUNK: usize = 8192;
+
+/// An async stream to the server, plain tcp or encrypted.
 /// The `ImapConnection` serves to register a request to the server
 /// and receive a response.
 ///
@@ -23,43 +47,559 @@ use tokio_util::codec::{Decoder, Framed, LinesCodec};
 ///
 /// We can wait multiple responses at one time in multiple threads
 /// because we can analyze received tag and find the corresponding Channel.
-pub(crate) struct ImapConnection {
-    sink: SplitSink<Framed<TcpStream, LinesCodec>, String>,
-    subscriptions: Arc<HashMap<Tag, Sender<String>>>,
+pub(crate) struct ImapConnection<S> {
+    sink: SplitSink<Framed<S, ImapCodec>, Bytes>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
     generator: TagGenerator,
-    // Cancel background listener future
-    cancel: JoinHandle<()>,
+    // Server greeting, the only response that is received before any command
+    greeting: Bytes,
+    // Stop background listener, it is stopped on drop too
+    shutdown: oneshot::Sender<()>,
+    // Background listener gives back its half of the stream when stopped
+    listener: JoinHandle<SplitStream<Framed<S, ImapCodec>>>,
+    // Untagged responses received without a pending command, updates
+    // not consumed by commands and `BYE`
+    unsolicited: broadcast::Sender<UntaggedResponse>,
+    // Tag of `IDLE` in progress and whether `DONE` was sent for it
+    idling: Option<(Tag, bool)>,
+}
+
+impl<S> std::fmt::Debug for ImapConnection<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImapConnection")
+            .field("subscriptions", &self.subscriptions)
+            .field("generator", &self.generator)
+            .field("greeting", &self.greeting)
+            .field("idling", &self.idling)
+            .finish()
+    }
 }
 
-impl ImapConnection {
-    async fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+/// Commands waiting for the tagged completion
+#[derive(Debug, Default)]
+struct Subscriptions {
+    pending: BTreeMap<Tag, Sender<Bytes>>,
+    // Background listener stopped, nothing will be received anymore
+    closed: bool,
+}
+
+impl Subscriptions {
+    /// Find a channel for the response. Untagged responses do not say which
+    /// command they belong to, so they are given to the oldest pending command.
+    /// Continuation requests can be sent only for the command which is being
+    /// transmitted, that is the newest one.
+    /// The subscription is removed when the tagged completion is received
+    fn route(&mut self, response: &[u8]) -> Option<Sender<Bytes>> {
+        match response_tag(response) {
+            Some(tag) => self.pending.remove(&tag),
+            None if response.starts_with(b"+") => self.pending.values().next_back().cloned(),
+            None => self.pending.values().next().cloned(),
+        }
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+        self.pending.clear();
+    }
+}
+
+/// Responses to one command. Untagged responses come first, the
+/// tagged completion is the last one
+#[derive(Debug)]
+pub(crate) struct Responses {
+    tag: Tag,
+    receiver: Receiver<Bytes>,
+    // Received while waiting for a continuation request
+    buffer: VecDeque<Bytes>,
+    unsolicited: broadcast::Sender<UntaggedResponse>,
+}
+
+impl Responses {
+    /// Tag of the command
+    pub(crate) fn tag(&self) -> &Tag {
+        &self.tag
+    }
+
+    /// Broadcast the mailbox or message update which the command did not
+    /// consume, other untagged data is dropped
+    pub(crate) fn forward(&self, data: &parser::UntaggedResponse<'_>) {
+        if is_update(data) {
+            // Fails only if there are no listeners
+            let _ = self.unsolicited.send(data.into());
+        }
+    }
+
+    /// Receive next complete response. Fails if the connection
+    /// was lost before the tagged completion
+    pub(crate) async fn next(&mut self) -> Result<Bytes> {
+        match self.buffer.pop_front() {
+            Some(response) => Ok(response),
+            None => self.receiver.recv().await.ok_or(Error::ConnectionLost),
+        }
+    }
+
+    /// Poll version of [Responses::next]
+    pub(crate) fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Result<Bytes>> {
+        match self.buffer.pop_front() {
+            Some(response) => Poll::Ready(Ok(response)),
+            None => self
+                .receiver
+                .poll_recv(cx)
+                .map(|response| response.ok_or(Error::ConnectionLost)),
+        }
+    }
+
+    /// Wait for the continuation request, other untagged responses are kept
+    /// for [Responses::next]. Fails with [Error::No] or [Error::Bad] if
+    /// the server completes the command instead
+    pub(crate) async fn continuation(&mut self) -> Result<Bytes> {
+        loop {
+            let response = self.receiver.recv().await.ok_or(Error::ConnectionLost)?;
+
+            if response.starts_with(b"+") {
+                return Ok(response);
+            }
+
+            if response_tag(&response).as_ref() == Some(&self.tag) {
+                return Err(rejected(&response));
+            }
+
+            self.buffer.push_back(response);
+        }
+    }
+
+    /// Wait for the next continuation request of the exchange which ends
+    /// with any completion, e.g. `AUTHENTICATE`. Returns `None` when
+    /// the completion is received, it is kept for [Responses::next]
+    pub(crate) async fn challenge(&mut self) -> Result<Option<Bytes>> {
+        loop {
+            let response = self.receiver.recv().await.ok_or(Error::ConnectionLost)?;
+
+            if response.starts_with(b"+") {
+                return Ok(Some(response));
+            }
+
+            let done = response_tag(&response).as_ref() == Some(&self.tag);
+            self.buffer.push_back(response);
+            if done {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+// resp-cond-bye, checked without parsing the whole response
+fn is_bye(response: &[u8]) -> bool {
+    response.len() >= 6 && response[..6].eq_ignore_ascii_case(b"* BYE ")
+}
+
+// Unilateral updates of the selected mailbox, RFC 3501 section 7
+fn is_update(data: &parser::UntaggedResponse<'_>) -> bool {
+    matches!(
+        data,
+        parser::UntaggedResponse::MailBox(
+            parser::MailBoxData::Flags(_)
+                | parser::MailBoxData::Exists(_)
+                | parser::MailBoxData::Recent(_)
+        ) | parser::UntaggedResponse::Message(_)
+            | parser::UntaggedResponse::Vanished(..)
+    )
+}
+
+// Tagged completion received instead of a continuation request
+fn rejected(response: &[u8]) -> Error {
+    match parse_response(response) {
+        Ok((_, ImapResponse::Response(TaggedResponse { resp, .. }))) => match resp.status {
+            ImapResult::No => Error::No(resp.text.text.to_owned()),
+            ImapResult::Bad => Error::Bad(resp.text.text.to_owned()),
+            ImapResult::Ok => create_custom_error(format!(
+                "Command completed without continuation: {}",
+                resp.text.text
+            )),
+        },
+        _ => Error::Parser(response.to_vec()),
+    }
+}
+
+impl ImapConnection<TcpStream> {
+    pub(crate) async fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self> {
         let connection = TcpStream::connect(addr).await?;
+        Self::new(connection).await
+    }
+}
+
+impl<S> ImapConnection<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    /// Wait for the greeting on the established stream
+    pub(crate) async fn new(stream: S) -> Result<Self> {
+        let mut frame = ImapCodec.framed(stream);
+
+        let greeting = match frame.next().await {
+            Some(response) => response?,
+            None => return Err(Error::ConnectionLost),
+        };
+        trace!("S: {:?}", greeting);
 
-        let frame = LinesCodec::default().framed(connection);
+        let (unsolicited, _) = broadcast::channel(UNSOLICITED_BUFFER);
 
+        Ok(Self::spawn(
+            frame,
+            greeting,
+            TagGenerator::default(),
+            unsolicited,
+        ))
+    }
+
+    fn spawn(
+        frame: Framed<S, ImapCodec>,
+        greeting: Bytes,
+        generator: TagGenerator,
+        unsolicited: broadcast::Sender<UntaggedResponse>,
+    ) -> Self {
         let (sink, mut stream) = frame.split();
 
-        let subscriptions = Arc::new(HashMap::new());
+        let subscriptions = Arc::new(Mutex::new(Subscriptions::default()));
+        let subs = subscriptions.clone();
+        let (shutdown, mut stop) = oneshot::channel();
+        let announce = unsolicited.clone();
 
-        let future = tokio::spawn(async move {
+        let listener = tokio::spawn(async move {
             loop {
-                let buf = stream.next().await.unwrap();
+                let buf = tokio::select! {
+                    _ = &mut stop => break,
+                    buf = stream.next() => match buf {
+                        Some(Ok(buf)) => buf,
+                        _ => break,
+                    },
+                };
                 trace!("S: {:?}", buf);
 
                 // Get in subs sender half by tag
+                let sender = subs.lock().unwrap().route(&buf);
+
+                // Nobody waits for the untagged data, and everybody
+                // should know that the server is closing the connection
+                if sender.is_none() || is_bye(&buf) {
+                    if let Ok((_, ImapResponse::Data(data))) = parse_response(&buf) {
+                        // Fails only if there are no listeners
+                        let _ = announce.send((&data).into());
+                    }
+                }
+
+                match sender {
+                    // Receiver could be dropped if nobody waits for the command anymore
+                    Some(sender) => {
+                        let _ = sender.send(buf).await;
+                    }
+                    None => trace!("Response without subscriber: {:?}", buf),
+                }
             }
+
+            subs.lock().unwrap().close();
+            stream
         });
 
-        Ok(Self {
+        Self {
             sink,
             subscriptions,
-            generator: TagGenerator::default(),
-            cancel: future,
-        })
+            generator,
+            greeting,
+            shutdown,
+            listener,
+            unsolicited,
+            idling: None,
+        }
+    }
+
+    /// Receive untagged responses which do not belong to any command,
+    /// mailbox updates forwarded by [Responses::forward], and `BYE`
+    /// whenever it is received
+    pub(crate) fn unsolicited(&self) -> broadcast::Receiver<UntaggedResponse> {
+        self.unsolicited.subscribe()
+    }
+
+    /// Server greeting
+    pub(crate) fn greeting(&self) -> &[u8] {
+        &self.greeting
+    }
+
+    /// Send `command` prefixed with a new unique tag and subscribe
+    /// to the responses
+    pub(crate) async fn send(&mut self, command: &Command<'_>) -> Result<Responses> {
+        self.transmit(command, None).await
     }
 
-    async fn send(&mut self, data: String) {
-        // Save subscription by tag to subscriptions map
-        self.sink.send(data).await.unwrap()
+    /// Send a line without a tag as a part of the command in progress,
+    /// e.g. `DONE` to finish `IDLE`
+    pub(crate) async fn send_line(&mut self, line: &[u8]) -> Result<()> {
+        let mut buf = BytesMut::with_capacity(line.len() + 2);
+        buf.extend_from_slice(line);
+        buf.extend_from_slice(b"\r\n");
+        trace!("C: {:?}", buf);
+
+        self.sink.send(buf.freeze()).await
+    }
+
+    /// Send `DONE` to finish `IDLE`
+    pub(crate) async fn done(&mut self) -> Result<()> {
+        self.send_line(b"DONE").await?;
+
+        if let Some((_, done)) = &mut self.idling {
+            *done = true;
+        }
+        Ok(())
+    }
+
+    /// Finish `IDLE` which was abandoned by the client, e.g. the stream was
+    /// dropped. Untagged responses received until the completion are
+    /// broadcast as unsolicited
+    async fn finish_idle(&mut self) -> Result<()> {
+        let (tag, done) = match self.idling.take() {
+            Some(idling) => idling,
+            None => return Ok(()),
+        };
+
+        // Responses of the abandoned command went to the dropped receiver
+        let (sender, mut receiver) = channel(RESPONSES_BUFFER);
+        {
+            let mut subscriptions = self.subscriptions.lock().unwrap();
+            if subscriptions.closed {
+                return Err(Error::ConnectionLost);
+            }
+            match subscriptions.pending.get_mut(&tag) {
+                Some(pending) => *pending = sender,
+                // Already completed
+                None => return Ok(()),
+            }
+        }
+
+        if !done {
+            self.send_line(b"DONE").await?;
+        }
+
+        loop {
+            let response = receiver.recv().await.ok_or(Error::ConnectionLost)?;
+
+            if response_tag(&response).as_ref() == Some(&tag) {
+                return Ok(());
+            }
+            if let Ok((_, ImapResponse::Data(data))) = parse_response(&response) {
+                let _ = self.unsolicited.send((&data).into());
+            }
+        }
+    }
+
+    /// Send `command` which ends with a literal header of `size` octets.
+    /// The literal data is streamed from `body` after the continuation request
+    pub(crate) async fn send_literal(
+        &mut self,
+        command: &Command<'_>,
+        size: u32,
+        body: &mut (dyn AsyncRead + Unpin + Send),
+    ) -> Result<Responses> {
+        self.transmit(command, Some((size, body))).await
+    }
+
+    async fn transmit(
+        &mut self,
+        command: &Command<'_>,
+        literal: Option<(u32, &mut (dyn AsyncRead + Unpin + Send))>,
+    ) -> Result<Responses> {
+        self.finish_idle().await?;
+
+        // Generator is an infinite iterator
+        let tag = self.generator.next().unwrap();
+        let (sender, receiver) = channel(RESPONSES_BUFFER);
+
+        // Save subscription by tag to subscriptions map before sending,
+        // so the response can not outrun it
+        {
+            let mut subscriptions = self.subscriptions.lock().unwrap();
+            if subscriptions.closed {
+                return Err(Error::ConnectionLost);
+            }
+            subscriptions.pending.insert(tag.clone(), sender);
+        }
+
+        let mut responses = Responses {
+            tag,
+            receiver,
+            buffer: VecDeque::new(),
+            unsolicited: self.unsolicited.clone(),
+        };
+
+        if let Err(e) = self.write(command, &mut responses, literal).await {
+            self.subscriptions
+                .lock()
+                .unwrap()
+                .pending
+                .remove(&responses.tag);
+            return Err(e);
+        }
+
+        if let Command::Idle = command {
+            self.idling = Some((responses.tag.clone(), false));
+        }
+        Ok(responses)
+    }
+
+    async fn write(
+        &mut self,
+        command: &Command<'_>,
+        responses: &mut Responses,
+        mut literal: Option<(u32, &mut (dyn AsyncRead + Unpin + Send))>,
+    ) -> Result<()> {
+        let parts = command.serialize(&responses.tag)?;
+        let last = parts.len() - 1;
+
+        for (i, part) in parts.into_iter().enumerate() {
+            // Streamed literal data goes right before the rest of the command
+            if i == last {
+                if let Some((size, body)) = literal.take() {
+                    self.stream(size, body).await?;
+                }
+            }
+
+            trace!("C: {:?}", part);
+            self.sink.send(part).await?;
+
+            // The part ends with a synchronizing literal header
+            if i < last {
+                responses.continuation().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn stream(&mut self, size: u32, body: &mut (dyn AsyncRead + Unpin + Send)) -> Result<()> {
+        let mut body = body.take(size.into());
+        let mut sent = 0;
+
+        loop {
+            let mut chunk = BytesMut::with_capacity(LITERAL_CHUNK);
+            let n = body.read_buf(&mut chunk).await?;
+            if n == 0 {
+                break;
+            }
+
+            sent += n;
+            self.sink.feed(chunk.freeze()).await?;
+        }
+        trace!("C: <{} octets>", sent);
+
+        // The server still waits for the rest of the literal, so the next
+        // command would become message data. The connection is closed
+        // and later commands fail with ConnectionLost
+        if sent != size as usize {
+            self.subscriptions.lock().unwrap().close();
+            let _ = self.sink.close().await;
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "literal data is shorter than its size",
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Stop the background listener and replace the underlying stream with
+    /// the result of `handshake`, e.g. after `STARTTLS`. Tags continue
+    /// the sequence of the old stream
+    pub(crate) async fn upgrade<T, F, Fut>(self, handshake: F) -> Result<ImapConnection<T>>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+        F: FnOnce(S) -> Fut,
+        Fut: Future<Output = std::io::Result<T>>,
+    {
+        let Self {
+            sink,
+            generator,
+            greeting,
+            shutdown,
+            listener,
+            unsolicited,
+            ..
+        } = self;
+
+        let _ = shutdown.send(());
+        let stream = listener
+            .await
+            .map_err(|e| create_custom_error(e.to_string()))?;
+
+        // Both halves come from one split, so reunite can not fail
+        let parts = sink.reunite(stream).unwrap().into_parts();
+
+        // Anything received before the handshake was not protected by it
+        if !parts.read_buf.is_empty() {
+            return Err(Error::Parser(parts.read_buf.to_vec()));
+        }
+
+        let stream = handshake(parts.io).await?;
+
+        Ok(ImapConnection::spawn(
+            ImapCodec.framed(stream),
+            greeting,
+            generator,
+            unsolicited,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImapConnection;
+    use crate::command::Command;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    #[tokio::test]
+    async fn concurrent_commands() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut reader = BufReader::new(reader);
+
+            writer.write_all(b"* OK ready\r\n").await.unwrap();
+
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "a0 NOOP\r\n");
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "a1 CAPABILITY\r\n");
+
+            writer
+                .write_all(b"a0 OK done\r\n* CAPABILITY IMAP4rev1\r\na1 OK done\r\n")
+                .await
+                .unwrap();
+        });
+
+        let mut connection = ImapConnection::connect(addr).await.unwrap();
+        assert_eq!(connection.greeting(), b"* OK ready\r\n");
+
+        let mut noop = connection.send(&Command::Noop).await.unwrap();
+        let mut capability = connection.send(&Command::Capability).await.unwrap();
+
+        // Both commands are in flight, wait for the second one first
+        assert_eq!(
+            capability.next().await.unwrap(),
+            &b"* CAPABILITY IMAP4rev1\r\n"[..]
+        );
+        assert_eq!(capability.next().await.unwrap(), &b"a1 OK done\r\n"[..]);
+        assert!(capability.next().await.is_err());
+
+        assert_eq!(noop.tag().to_string(), "a0");
+        assert_eq!(noop.next().await.unwrap(), &b"a0 OK done\r\n"[..]);
+        assert!(noop.next().await.is_err());
+
+        server.await.unwrap();
     }
 }
@@ -4,7 +4,14 @@
 #![allow(dead_code)] /* allow on develop stage */
 
 pub mod client;
+mod codec;
+mod command;
 pub mod error;
+pub mod fetch;
 mod imapconnection;
 mod parser;
+pub mod sasl;
+pub mod search;
 mod tag;
+pub mod tls;
+pub mod types;
@@ -3,17 +3,34 @@
 mod rfc3501;
 #[cfg(test)]
 mod tests;
-mod types;
+pub(crate) mod types;
 
-use rfc3501::{continue_req, greeting, response_tagged};
+pub(crate) use rfc3501::core::{
+    is_astring_char, is_atom_char, is_list_char, is_quoted_char, response_frame,
+};
+
+use crate::tag::Tag;
+use rfc3501::{continue_req, grammar::imap_tag, greeting, response_data, response_tagged};
 use types::ImapResponse;
 
-use nom::{branch::alt, combinator::map, IResult};
+use nom::{branch::alt, bytes::streaming::tag, combinator::map, sequence::terminated, IResult};
 
 pub(crate) fn parse(i: &[u8]) -> IResult<&[u8], ImapResponse<'_>> {
+    alt((map(greeting, ImapResponse::Greeting), parse_response))(i)
+}
+
+// Untagged `OK` and `BYE` responses are indistinguishable from a greeting,
+// so after the greeting was received responses must be parsed without it
+pub(crate) fn parse_response(i: &[u8]) -> IResult<&[u8], ImapResponse<'_>> {
     alt((
-        map(greeting, |v| ImapResponse::Greeting(v)),
-        map(continue_req, |v| ImapResponse::Continue(v)),
-        map(response_tagged, |v| ImapResponse::Response(v)),
+        map(continue_req, ImapResponse::Continue),
+        map(response_tagged, ImapResponse::Response),
+        map(response_data, ImapResponse::Data),
     ))(i)
 }
+
+/// Tag of the tagged response. Only the beginning of the response is checked,
+/// so the tag is found even if the rest of the response is malformed
+pub(crate) fn response_tag(i: &[u8]) -> Option<Tag> {
+    terminated(imap_tag, tag(" "))(i).ok().map(|(_, t)| t)
+}
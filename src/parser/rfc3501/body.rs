@@ -1,35 +1,70 @@
 //! Body IMAP grammar
 
 use super::{core::*, grammar::envelope};
+use std::borrow::Cow;
 
 use crate::parser::types::{
-    Body, BodyEnc, BodyFields, BodyTypeBasic, BodyTypeMsg, BodyTypeText, MediaBasic, MediaType,
+    Body, BodyDisposition, BodyEnc, BodyExt1Part, BodyExtMpart, BodyExtension, BodyFields,
+    BodyParams, BodyTypeBasic, BodyTypeMpart, BodyTypeMsg, BodyTypeText, MediaBasic, MediaType,
 };
 
 use nom::{
     branch::alt,
     bytes::streaming::{tag, tag_no_case},
-    combinator::{map, value},
-    multi::separated_list1,
-    sequence::{delimited, preceded, separated_pair, terminated, tuple},
+    combinator::{map, opt, value},
+    multi::{many0, many1, separated_list1},
+    sequence::{delimited, preceded, separated_pair, tuple},
     IResult,
 };
 
 // body = '(' (body-type-1part | body-type-mpart) ')'
-pub(crate) fn body(i: &[u8]) -> IResult<&[u8], Body> {
-    todo!()
+pub(crate) fn body(i: &[u8]) -> IResult<&[u8], Body<'_>> {
+    delimited(
+        tag("("),
+        alt((body_type_1part, map(body_type_mpart, Body::Multipart))),
+        tag(")"),
+    )(i)
 }
 
 // body-type-1part = (body-type-basic | body-type-msg | body-type-text) [SP body-ext-1part]
+// Extension data is parsed by every type parser and stored in the type
 pub(crate) fn body_type_1part(i: &[u8]) -> IResult<&[u8], Body<'_>> {
-    todo!()
+    // body-type-basic accepts "MESSAGE" and "TEXT" too, so it must be the last
+    alt((
+        map(body_type_msg, Body::Msg),
+        map(body_type_text, Body::Text),
+        map(body_type_basic, Body::Basic),
+    ))(i)
+}
+
+// body-type-mpart = 1*body SP media-subtype [SP body-ext-mpart]
+// media-subtype = string
+pub(crate) fn body_type_mpart(i: &[u8]) -> IResult<&[u8], BodyTypeMpart<'_>> {
+    map(
+        tuple((
+            many1(body),
+            tag(" "),
+            string,
+            opt(preceded(tag(" "), body_ext_mpart)),
+        )),
+        |(bodies, _, subtype, ext)| BodyTypeMpart {
+            bodies,
+            subtype,
+            ext,
+        },
+    )(i)
 }
 
 // body_type_basic = media-basic SP body-fields
 pub(crate) fn body_type_basic(i: &[u8]) -> IResult<&[u8], BodyTypeBasic<'_>> {
     map(
-        separated_pair(media_basic, tag(" "), body_fields),
-        |(media, fields)| BodyTypeBasic { media, fields },
+        tuple((
+            media_basic,
+            tag(" "),
+            body_fields,
+            opt(preceded(tag(" "), body_ext_1part)),
+        )),
+        |(media, _, fields, ext)| BodyTypeBasic { media, fields, ext },
     )(i)
 }
 
@@ -91,7 +126,7 @@ pub(crate) fn body_fields(i: &[u8]) -> IResult<&[u8], BodyFields<'_>> {
 }
 
 // body-fld-param = '(' string SP string *(SP string SP string) ')' | nil
-pub(crate) fn body_fld_param(i: &[u8]) -> IResult<&[u8], Option<Vec<(&str, &str)>>> {
+pub(crate) fn body_fld_param(i: &[u8]) -> IResult<&[u8], Option<BodyParams<'_>>> {
     alt((
         map(
             delimited(
@@ -138,12 +173,14 @@ pub(crate) fn body_type_msg(i: &[u8]) -> IResult<&[u8], BodyTypeMsg<'_>> {
             body,
             tag(" "),
             number,
+            opt(preceded(tag(" "), body_ext_1part)),
         )),
-        |(_, fields, _, envelope, _, body, _, lines)| BodyTypeMsg {
+        |(_, fields, _, envelope, _, body, _, lines, ext)| BodyTypeMsg {
             fields,
             envelope,
             body: Box::new(body),
             lines,
+            ext,
         },
     )(i)
 }
@@ -161,11 +198,13 @@ pub(crate) fn body_type_text(i: &[u8]) -> IResult<&[u8], BodyTypeText<'_>> {
             body_fields,
             tag(" "),
             number,
+            opt(preceded(tag(" "), body_ext_1part)),
         )),
-        |(_, subtype, _, fields, _, lines)| BodyTypeText {
+        |(_, subtype, _, fields, _, lines, ext)| BodyTypeText {
             subtype,
             fields,
             lines,
+            ext,
         },
     )(i)
 }
@@ -174,15 +213,112 @@ pub(crate) fn body_type_text(i: &[u8]) -> IResult<&[u8], BodyTypeText<'_>> {
 //                  body-extension)]]]
 // ; MUST NOT be returned on non-extensible "BODY" fetch
 // body-fld-md5, body_fld_loc = nstring
-pub(crate) fn body_ext_1part(i: &[u8]) -> IResult<&[u8], ()> {
-    todo!()
+pub(crate) fn body_ext_1part(i: &[u8]) -> IResult<&[u8], BodyExt1Part<'_>> {
+    map(tuple((nstring, body_ext_tail)), |(md5, tail)| {
+        let (dsp, lang, loc, extension) = tail;
+        BodyExt1Part {
+            md5,
+            dsp,
+            lang,
+            loc,
+            extension,
+        }
+    })(i)
+}
+
+// body-ext-mpart = body-fld-param [SP body-fld-dsp [SP body-fld-lang [SP body-fld-loc *(SP
+//                  body-extension)]]]
+// ; MUST NOT be returned on non-extensible "BODY" fetch
+pub(crate) fn body_ext_mpart(i: &[u8]) -> IResult<&[u8], BodyExtMpart<'_>> {
+    map(tuple((body_fld_param, body_ext_tail)), |(param, tail)| {
+        let (dsp, lang, loc, extension) = tail;
+        BodyExtMpart {
+            param,
+            dsp,
+            lang,
+            loc,
+            extension,
+        }
+    })(i)
+}
+
+type BodyExtTail<'a> = (
+    Option<BodyDisposition<'a>>,
+    Option<Vec<Cow<'a, str>>>,
+    Option<Cow<'a, str>>,
+    Vec<BodyExtension<'a>>,
+);
+
+// Common part of body-ext-1part and body-ext-mpart:
+// [SP body-fld-dsp [SP body-fld-lang [SP body-fld-loc *(SP body-extension)]]]
+fn body_ext_tail(i: &[u8]) -> IResult<&[u8], BodyExtTail<'_>> {
+    map(
+        opt(preceded(
+            tag(" "),
+            tuple((
+                body_fld_dsp,
+                opt(preceded(
+                    tag(" "),
+                    tuple((
+                        body_fld_lang,
+                        opt(preceded(
+                            tag(" "),
+                            tuple((nstring, many0(preceded(tag(" "), body_extension)))),
+                        )),
+                    )),
+                )),
+            )),
+        )),
+        |tail| match tail {
+            None => (None, None, None, vec![]),
+            Some((dsp, None)) => (dsp, None, None, vec![]),
+            Some((dsp, Some((lang, None)))) => (dsp, lang, None, vec![]),
+            Some((dsp, Some((lang, Some((loc, extension)))))) => (dsp, lang, loc, extension),
+        },
+    )(i)
 }
 
 // body-fld-dsp = '(' string SP body-fld-param ')' | nil
+pub(crate) fn body_fld_dsp(i: &[u8]) -> IResult<&[u8], Option<BodyDisposition<'_>>> {
+    alt((
+        map(
+            delimited(
+                tag("("),
+                separated_pair(string, tag(" "), body_fld_param),
+                tag(")"),
+            ),
+            |(kind, param)| Some(BodyDisposition { kind, param }),
+        ),
+        nil,
+    ))(i)
+}
 
 // body-fld-lang = nstring | '(' string *(SP string) ')'
+pub(crate) fn body_fld_lang(i: &[u8]) -> IResult<&[u8], Option<Vec<Cow<'_, str>>>> {
+    alt((
+        map(nstring, |v| v.map(|lang| vec![lang])),
+        map(
+            delimited(tag("("), separated_list1(tag(" "), string), tag(")")),
+            Some,
+        ),
+    ))(i)
+}
 
 // body-extension = nstring | number | '(' body-extension *(SP body-extension) ')'
 // ; Future expansion. Client implemenations MUST accept body-extension fields.
 // Server implemenations MUST NOT generate body-extension fields except
 // as defined by future standart or standards-track revisions of rfc3501
+pub(crate) fn body_extension(i: &[u8]) -> IResult<&[u8], BodyExtension<'_>> {
+    alt((
+        map(nstring, BodyExtension::NString),
+        map(number, BodyExtension::Number),
+        map(
+            delimited(
+                tag("("),
+                separated_list1(tag(" "), body_extension),
+                tag(")"),
+            ),
+            BodyExtension::List,
+        ),
+    ))(i)
+}
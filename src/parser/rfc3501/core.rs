@@ -1,18 +1,18 @@
 //! IMAP core types
 
-use std::{fmt::Debug, str::FromStr};
+use std::{borrow::Cow, fmt::Debug, str::FromStr};
 
 use nom::{
     branch::alt,
-    bytes::streaming::{tag, tag_no_case, take_while, take_while1, take_while_m_n},
+    bytes::streaming::{tag, tag_no_case, take, take_until, take_while1, take_while_m_n},
     character::is_alphanumeric,
     character::{
         is_digit,
-        streaming::{crlf, u32},
+        streaming::{crlf, u32, u64},
     },
-    combinator::{map, map_res, not, opt, peek, value},
-    multi::{length_data, many1_count},
-    sequence::{delimited, tuple},
+    combinator::{map, map_res, not, opt, recognize},
+    multi::{many0_count, many1_count},
+    sequence::{delimited, preceded, terminated, tuple},
     IResult,
 };
 
@@ -79,7 +79,7 @@ fn is_base64_char(i: u8) -> bool {
 
 // atom-specials = '(' | ')' | '{' | SP | CTL | list-wildcards | quoted-specials | resp-specials
 fn is_atom_specials(i: u8) -> bool {
-    i == b')'
+    i == b'('
         || i == b')'
         || i == b'{'
         || is_space(i)
@@ -90,7 +90,7 @@ fn is_atom_specials(i: u8) -> bool {
 }
 
 // ATOM-CHAR = <any CHAR except atom-specials>
-fn is_atom_char(i: u8) -> bool {
+pub(crate) fn is_atom_char(i: u8) -> bool {
     !is_atom_specials(i) && is_char(i)
 }
 
@@ -99,16 +99,24 @@ pub(crate) fn is_astring_char(i: u8) -> bool {
     is_atom_char(i) || is_resp_specials(i)
 }
 
+// list-char = ATOM-CHAR | list-wildcards | resp-specials
+pub(crate) fn is_list_char(i: u8) -> bool {
+    is_atom_char(i) || is_list_wildcards(i) || is_resp_specials(i)
+}
+
 // nil = 'NIL'
 pub(crate) fn nil<T>(i: &[u8]) -> IResult<&[u8], Option<T>> {
     map(tag_no_case("NIL"), |_| None)(i)
 }
 
 // astring = 1*ASTRING-CHAR | string
-pub(crate) fn astring(i: &[u8]) -> IResult<&[u8], &str> {
+pub(crate) fn astring(i: &[u8]) -> IResult<&[u8], Cow<'_, str>> {
     alt((
         string,
-        map_res(take_while1(is_astring_char), std::str::from_utf8),
+        map(
+            map_res(take_while1(is_astring_char), std::str::from_utf8),
+            Cow::Borrowed,
+        ),
     ))(i)
 }
 
@@ -120,10 +128,41 @@ pub(crate) fn atom(i: &[u8]) -> IResult<&[u8], &str> {
 // literal = "{" number "}" CRLF *CHAR8;
 // number represents the number of CHAR8s
 pub(crate) fn literal(i: &[u8]) -> IResult<&[u8], &str> {
+    map_res(literal_bytes, std::str::from_utf8)(i)
+}
+
+// literal without utf-8 check, message content is not required to be utf-8
+pub(crate) fn literal_bytes(i: &[u8]) -> IResult<&[u8], &[u8]> {
     let (i, (_, count, _, _)) = tuple((tag("{"), number, tag("}"), crlf))(i)?;
-    let parser = take_while_m_n(count as usize, count as usize, is_char8);
+    take_while_m_n(count as usize, count as usize, is_char8)(i)
+}
+
+// Size of the literal which ends the line: ... "{" number "}"
+fn literal_size(line: &[u8]) -> Option<usize> {
+    let start = line.iter().rposition(|&c| c == b'{')?;
+    match delimited(tag("{"), number, tag("}"))(&line[start..]) {
+        Ok((&[], count)) => Some(count as usize),
+        _ => None,
+    }
+}
+
+// One complete server response: lines up to CRLF with all literals they
+// introduce. Returns Incomplete until the whole response is available
+pub(crate) fn response_frame(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    let mut rest = i;
 
-    map_res(parser, std::str::from_utf8)(i)
+    loop {
+        let (remainder, line) = terminated(take_until("\r\n"), crlf)(rest)?;
+        rest = remainder;
+
+        match literal_size(line) {
+            Some(count) => rest = take(count)(rest)?.0,
+            None => break,
+        }
+    }
+
+    let length = i.len() - rest.len();
+    Ok((rest, &i[..length]))
 }
 
 // text = 1*TEXT-CHAR
@@ -132,48 +171,96 @@ pub(crate) fn text(i: &[u8]) -> IResult<&[u8], &str> {
     map_res(take_while1(is_text_char), std::str::from_utf8)(i)
 }
 
-// quoted = DQUOTE *QUOTED-CHAR DQUOTE;
-// quoted text
-pub(crate) fn quoted(i: &[u8]) -> IResult<&[u8], &str> {
+// 1*<any TEXT-CHAR except ']'>, text of the unknown response code
+pub(crate) fn code_text(i: &[u8]) -> IResult<&[u8], &str> {
     map_res(
-        delimited(tag("\""), take_while(is_quoted_char), tag("\"")),
+        take_while1(|c| is_text_char(c) && !is_resp_specials(c)),
         std::str::from_utf8,
     )(i)
 }
 
+// quoted = DQUOTE *QUOTED-CHAR DQUOTE;
+// quoted text with escaped quoted-specials replaced by themselves
+pub(crate) fn quoted(i: &[u8]) -> IResult<&[u8], Cow<'_, str>> {
+    map(map_res(quoted_bytes, std::str::from_utf8), unescape)(i)
+}
+
+// Literals are sent as is, only quoted strings are escaped
+fn unescape(s: &str) -> Cow<'_, str> {
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+
+    Cow::Owned(result)
+}
+
+// QUOTED-CHAR = <any TEXT-CHAR except quoted-specials> | '\' quoted-specials
+pub(crate) fn quoted_bytes(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    delimited(
+        tag("\""),
+        recognize(many0_count(alt((
+            take_while1(|c| is_text_char(c) && !is_quoted_specials(c)),
+            preceded(tag("\\"), take_while_m_n(1, 1, is_quoted_specials)),
+        )))),
+        tag("\""),
+    )(i)
+}
+
 // string = quoted | literal
 //
-pub(crate) fn string(i: &[u8]) -> IResult<&[u8], &str> {
-    alt((quoted, literal))(i)
+pub(crate) fn string(i: &[u8]) -> IResult<&[u8], Cow<'_, str>> {
+    alt((quoted, map(literal, Cow::Borrowed)))(i)
+}
+
+// string without utf-8 check
+pub(crate) fn string_bytes(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    alt((quoted_bytes, literal_bytes))(i)
 }
 
 // nstring = string | nil
 // nil = 'NIL'
-pub(crate) fn nstring(i: &[u8]) -> IResult<&[u8], Option<&str>> {
+pub(crate) fn nstring(i: &[u8]) -> IResult<&[u8], Option<Cow<'_, str>>> {
     alt((map(string, Some), nil))(i)
 }
 
+// nstring without utf-8 check
+pub(crate) fn nstring_bytes(i: &[u8]) -> IResult<&[u8], Option<&[u8]>> {
+    alt((map(string_bytes, Some), nil))(i)
+}
+
 // base64-terminal = (2base64-char '==') | (3base64-char '=')
 pub(crate) fn base64_terminal(i: &[u8]) -> IResult<&[u8], &str> {
     map_res(
-        length_data(many1_count(peek(alt((
+        recognize(alt((
             tuple((take_while_m_n(2, 2, is_base64_char), tag("=="))),
             tuple((take_while_m_n(3, 3, is_base64_char), tag("="))),
-        ))))),
+        ))),
         std::str::from_utf8,
     )(i)
 }
 
 // base64 = *(4base64_char) [base64_terminal]
+// Empty base64 is not accepted, it can't be distinguished from empty text
 pub(crate) fn base64(i: &[u8]) -> IResult<&[u8], &str> {
-    // ODO: Check it
-    map_res(
-        length_data(many1_count(peek(tuple((
-            take_while_m_n(4, 4, is_base64_char),
-            opt(base64_terminal),
-        ))))),
-        std::str::from_utf8,
-    )(i)
+    alt((
+        map_res(
+            recognize(tuple((
+                many1_count(take_while_m_n(4, 4, is_base64_char)),
+                opt(base64_terminal),
+            ))),
+            std::str::from_utf8,
+        ),
+        base64_terminal,
+    ))(i)
 }
 
 // numbers
@@ -191,6 +278,12 @@ pub(crate) fn nz_number(i: &[u8]) -> IResult<&[u8], u32> {
     Ok((i, result))
 }
 
+// mod-sequence-value = 1*DIGIT, RFC 7162
+// ; positive unsigned 63-bit integer
+pub(crate) fn mod_sequence_value(i: &[u8]) -> IResult<&[u8], u64> {
+    u64(i)
+}
+
 // Help function for syntax like mDIGIT
 pub(crate) fn fixed_num<T: FromStr>(m: usize) -> impl Fn(&[u8]) -> IResult<&[u8], T>
 where
@@ -1,18 +1,19 @@
 //! IMAP grammar [rfc3501]
 
-use std::char::from_u32;
+use std::{borrow::Cow, char::from_u32};
 
-use super::core::*;
+use super::{body::body, core::*};
 use crate::parser::types::{
-    Address, ByeResponse, Capability, DateTime, Envelope, Flag, ImapResult, ListFlag, ListMailBox,
-    MailBoxData, MessageData, Month, MsgAtt, MsgFlag, RespCond, RespText, RespTextCode, StatusInfo,
-    StatusResponse, Time,
+    Address, ByeResponse, Capability, DateTime, Envelope, Flag, IdParams, ImapResult, ListFlag,
+    ListMailBox, MailBoxData, MessageData, Month, MsgAtt, MsgFlag, RespCond, RespText,
+    RespTextCode, Section, SectionText, StatusInfo, StatusResponse, Time,
 };
 use crate::tag::Tag;
+use crate::types::{SeqNumber, SequenceSet};
 use nom::{
     branch::alt,
     bytes::streaming::{tag, tag_no_case, take_while_m_n},
-    combinator::{map, map_res, opt, value},
+    combinator::{map, map_res, opt, recognize, value},
     multi::{many0, many1, separated_list0, separated_list1},
     sequence::{delimited, preceded, separated_pair, terminated, tuple},
     IResult,
@@ -20,24 +21,44 @@ use nom::{
 
 //message-data = nz-number SP ("EXPUNGE" | ("FETCH" SP msg-att))
 pub(crate) fn message_data(i: &[u8]) -> IResult<&[u8], MessageData<'_>> {
-    todo!()
+    let (i, (number, _)) = tuple((nz_number, tag(" ")))(i)?;
+
+    alt((
+        map(tag_no_case("EXPUNGE"), move |_| {
+            MessageData::Expunge(number)
+        }),
+        map(preceded(tag_no_case("FETCH "), msg_att), move |v| {
+            MessageData::Fetch(number, v)
+        }),
+    ))(i)
 }
 
 // msg_att = '(' (msg-att-dynamic | msg-att-static) *(SP (msg-att-dynamic | msg-att-static)) ')'
-pub(crate) fn msg_att(i: &[u8]) -> IResult<&[u8], MsgAtt<'_>> {
-    todo!()
+pub(crate) fn msg_att(i: &[u8]) -> IResult<&[u8], Vec<MsgAtt<'_>>> {
+    delimited(
+        tag("("),
+        separated_list1(tag(" "), alt((msg_att_dynamic, msg_att_static))),
+        tag(")"),
+    )(i)
 }
 
-// msg-att-dynamic = 'FLAGS' SP '(' [flag-fetch *(SP flag-fetch)] ')'
+// msg-att-dynamic = 'FLAGS' SP '(' [flag-fetch *(SP flag-fetch)] ')' |
+//                   'MODSEQ' SP '(' mod-sequence-value ')'
 // ; May change for a message
 pub(crate) fn msg_att_dynamic(i: &[u8]) -> IResult<&[u8], MsgAtt<'_>> {
-    map(
-        preceded(
-            tag_no_case("FLAGS "),
-            delimited(tag("("), separated_list1(tag(" "), flag_fetch), tag(")")),
+    alt((
+        map(
+            preceded(
+                tag_no_case("FLAGS "),
+                delimited(tag("("), separated_list0(tag(" "), flag_fetch), tag(")")),
+            ),
+            MsgAtt::Flags,
         ),
-        MsgAtt::Flags,
-    )(i)
+        map(
+            delimited(tag_no_case("MODSEQ ("), mod_sequence_value, tag(")")),
+            MsgAtt::ModSeq,
+        ),
+    ))(i)
 }
 
 // flag-fetch = flag | '\Recent'
@@ -64,22 +85,111 @@ pub(crate) fn msg_att_static(i: &[u8]) -> IResult<&[u8], MsgAtt<'_>> {
             preceded(tag_no_case("INTERNALDATE "), date_time),
             MsgAtt::InternalDate,
         ),
-        map(preceded(tag_no_case("RFC822 "), nstring), MsgAtt::Rfc822),
         map(
-            preceded(tag_no_case("RFC822.HEADER "), nstring),
+            preceded(tag_no_case("RFC822 "), nstring_bytes),
+            MsgAtt::Rfc822,
+        ),
+        map(
+            preceded(tag_no_case("RFC822.HEADER "), nstring_bytes),
             MsgAtt::Rfc822Header,
         ),
         map(
-            preceded(tag_no_case("RFC822.TEXT "), nstring),
+            preceded(tag_no_case("RFC822.TEXT "), nstring_bytes),
             MsgAtt::Rfc822Text,
         ),
         map(
             preceded(tag_no_case("RFC822.SIZE "), number),
             MsgAtt::Rfc822Size,
         ),
+        map(preceded(tag_no_case("BODY "), body), MsgAtt::Body),
+        map(
+            preceded(tag_no_case("BODYSTRUCTURE "), body),
+            MsgAtt::BodyStructure,
+        ),
+        map(
+            tuple((
+                tag_no_case("BODY"),
+                section,
+                opt(delimited(tag("<"), number, tag(">"))),
+                tag(" "),
+                nstring_bytes,
+            )),
+            |(_, section, origin, _, data)| MsgAtt::BodySection {
+                section,
+                origin,
+                data,
+            },
+        ),
+        map(preceded(tag_no_case("UID "), uniqueid), MsgAtt::Uid),
+    ))(i)
+}
+
+// uniqueid = nz-number
+// ; Strictly ascending
+pub(crate) fn uniqueid(i: &[u8]) -> IResult<&[u8], u32> {
+    nz_number(i)
+}
+
+// section = '[' [section-spec] ']'
+// section-spec = section-msgtext | (section-part ['.' section-text])
+pub(crate) fn section(i: &[u8]) -> IResult<&[u8], Section<'_>> {
+    map(
+        delimited(
+            tag("["),
+            opt(alt((
+                map(
+                    tuple((section_part, opt(preceded(tag("."), section_text)))),
+                    |(part, text)| Section { part, text },
+                ),
+                map(section_msgtext, |text| Section {
+                    part: vec![],
+                    text: Some(text),
+                }),
+            ))),
+            tag("]"),
+        ),
+        Option::unwrap_or_default,
+    )(i)
+}
+
+// section-part = nz-number *('.' nz-number)
+// ; body part nesting
+pub(crate) fn section_part(i: &[u8]) -> IResult<&[u8], Vec<u32>> {
+    separated_list1(tag("."), nz_number)(i)
+}
+
+// section-msgtext = 'HEADER' | 'HEADER.FIELDS' ['.NOT'] SP header-list | 'TEXT'
+// ; top-level or MESSAGE/RFC822 part
+pub(crate) fn section_msgtext(i: &[u8]) -> IResult<&[u8], SectionText<'_>> {
+    alt((
+        map(
+            preceded(tag_no_case("HEADER.FIELDS.NOT "), header_list),
+            SectionText::HeaderFieldsNot,
+        ),
+        map(
+            preceded(tag_no_case("HEADER.FIELDS "), header_list),
+            SectionText::HeaderFields,
+        ),
+        value(SectionText::Header, tag_no_case("HEADER")),
+        value(SectionText::Text, tag_no_case("TEXT")),
+    ))(i)
+}
+
+// section-text = section-msgtext | 'MIME'
+// ; text other than actual body part (headers, etc.)
+pub(crate) fn section_text(i: &[u8]) -> IResult<&[u8], SectionText<'_>> {
+    alt((
+        section_msgtext,
+        value(SectionText::Mime, tag_no_case("MIME")),
     ))(i)
 }
 
+// header-list = '(' header-fld-name *(SP header-fld-name) ')'
+// header-fld-name = astring
+pub(crate) fn header_list(i: &[u8]) -> IResult<&[u8], Vec<Cow<'_, str>>> {
+    delimited(tag("("), separated_list1(tag(" "), astring), tag(")"))(i)
+}
+
 // envelope = '(' env-date SP env-subject SP env-from SP env-sender
 //            SP env-reply-to SP env-to SP env-cc SP env-bcc SP env-in-reply-to
 //            SP env-message-id ')'
@@ -291,8 +401,8 @@ pub(crate) fn mailbox_data(i: &[u8]) -> IResult<&[u8], MailBoxData<'_>> {
             MailBoxData::Lsub,
         ),
         map(
-            preceded(tag_no_case("SEARCH "), mailbox_data_search),
-            MailBoxData::Search,
+            preceded(tag_no_case("SEARCH"), mailbox_data_search),
+            |(numbers, modseq)| MailBoxData::Search(numbers, modseq),
         ),
         map(
             preceded(tag_no_case("STATUS "), mailbox_data_status),
@@ -324,31 +434,83 @@ pub(crate) fn mailbox_list(i: &[u8]) -> IResult<&[u8], ListMailBox<'_>> {
         tuple((
             delimited(tag("("), opt(mbx_list_flags), tag(")")),
             tag(" "),
-            alt((
-                map(
-                    delimited(tag("\""), take_while_m_n(1, 1, is_quoted_char), tag("\"")),
-                    Some,
-                ),
-                nil,
-            )),
+            alt((map(quoted, Some), nil)),
             tag(" "),
             mailbox,
         )),
         |(flags, _, delimiter, _, name)| ListMailBox {
-            flags: match flags {
-                Some(v) => v,
-                None => vec![],
-            },
-            // SAFETY: is_quoted_char is valid ascii character, so it is valid utf-8
-            delimiter: unsafe { delimiter.map(|v| std::str::from_utf8_unchecked(v)) },
+            flags: flags.unwrap_or_default(),
+            delimiter,
             name,
         },
     )(i)
 }
 
-// *(SP nz-number)
-pub(crate) fn mailbox_data_search(i: &[u8]) -> IResult<&[u8], Vec<u32>> {
-    separated_list1(tag(" "), nz_number)(i)
+// *(SP nz-number) [SP search-sort-mod-seq]
+// search-sort-mod-seq = '(' 'MODSEQ' SP mod-sequence-value ')'
+// Some servers send a trailing space
+pub(crate) fn mailbox_data_search(i: &[u8]) -> IResult<&[u8], (Vec<u32>, Option<u64>)> {
+    terminated(
+        tuple((
+            many0(preceded(tag(" "), nz_number)),
+            opt(delimited(
+                tag_no_case(" (MODSEQ "),
+                mod_sequence_value,
+                tag(")"),
+            )),
+        )),
+        opt(tag(" ")),
+    )(i)
+}
+
+// seq-number = nz-number | '*'
+fn seq_number(i: &[u8]) -> IResult<&[u8], SeqNumber> {
+    alt((
+        map(nz_number, SeqNumber::Value),
+        value(SeqNumber::Largest, tag("*")),
+    ))(i)
+}
+
+// sequence-set = (seq-number | seq-range) *(',' sequence-set)
+// seq-range = seq-number ':' seq-number
+pub(crate) fn sequence_set(i: &[u8]) -> IResult<&[u8], SequenceSet> {
+    map(
+        separated_list1(
+            tag(","),
+            tuple((seq_number, opt(preceded(tag(":"), seq_number)))),
+        ),
+        |ranges| {
+            let mut set = SequenceSet::new();
+            for (start, end) in ranges {
+                set.insert_range(start, end.unwrap_or(start));
+            }
+            set
+        },
+    )(i)
+}
+
+// uid-set = (uniqueid / uid-range) *("," uid-set), RFC 4315
+// uid-range = uniqueid ":" uniqueid
+// Identifiers are listed in order of the response, unlike sequence-set
+// `*` is not allowed and a range may be descending
+pub(crate) fn uid_set(i: &[u8]) -> IResult<&[u8], Vec<u32>> {
+    map(
+        separated_list1(
+            tag(","),
+            tuple((nz_number, opt(preceded(tag(":"), nz_number)))),
+        ),
+        |ranges| {
+            let mut uids = vec![];
+            for (start, end) in ranges {
+                match end {
+                    Some(end) if end < start => uids.extend((end..=start).rev()),
+                    Some(end) => uids.extend(start..=end),
+                    None => uids.push(start),
+                }
+            }
+            uids
+        },
+    )(i)
 }
 
 // mailbox SP '(' [status-att-list] ')'
@@ -359,12 +521,9 @@ pub(crate) fn mailbox_data_status(i: &[u8]) -> IResult<&[u8], StatusResponse<'_>
             tag(" "),
             delimited(tag("("), opt(status_att_list), tag(")")),
         ),
-        |(name, status)| {
-            let status = match status {
-                Some(v) => v,
-                None => vec![],
-            };
-            StatusResponse { name, status }
+        |(name, status)| StatusResponse {
+            name,
+            status: status.unwrap_or_default(),
         },
     )(i)
 }
@@ -372,23 +531,20 @@ pub(crate) fn mailbox_data_status(i: &[u8]) -> IResult<&[u8], StatusResponse<'_>
 // 'MESSAGES' SP number
 pub(crate) fn status_messages(i: &[u8]) -> IResult<&[u8], StatusInfo> {
     map(
-        preceded(tag_no_case("MESSAGES "), nz_number),
+        preceded(tag_no_case("MESSAGES "), number),
         StatusInfo::Messages,
     )(i)
 }
 
 // 'RECENT' SP number
 pub(crate) fn status_recent(i: &[u8]) -> IResult<&[u8], StatusInfo> {
-    map(
-        preceded(tag_no_case("RECENT "), nz_number),
-        StatusInfo::Recent,
-    )(i)
+    map(preceded(tag_no_case("RECENT "), number), StatusInfo::Recent)(i)
 }
 
 // 'UIDNEXT' SP number
 pub(crate) fn status_uidnext(i: &[u8]) -> IResult<&[u8], StatusInfo> {
     map(
-        preceded(tag_no_case("UIDNEXT "), nz_number),
+        preceded(tag_no_case("UIDNEXT "), number),
         StatusInfo::UidNext,
     )(i)
 }
@@ -396,34 +552,43 @@ pub(crate) fn status_uidnext(i: &[u8]) -> IResult<&[u8], StatusInfo> {
 // 'UIDVALIDITY' SP number
 pub(crate) fn status_uidvalidity(i: &[u8]) -> IResult<&[u8], StatusInfo> {
     map(
-        preceded(tag_no_case("UIDVALIDITY "), nz_number),
+        preceded(tag_no_case("UIDVALIDITY "), number),
         StatusInfo::UidValidity,
     )(i)
 }
 
-// 'Unseen' SP number
+// 'UNSEEN' SP number
 pub(crate) fn status_unseen(i: &[u8]) -> IResult<&[u8], StatusInfo> {
+    map(preceded(tag_no_case("UNSEEN "), number), StatusInfo::Unseen)(i)
+}
+
+// 'HIGHESTMODSEQ' SP mod-sequence-value, RFC 7162
+pub(crate) fn status_highestmodseq(i: &[u8]) -> IResult<&[u8], StatusInfo> {
     map(
-        preceded(tag_no_case("UNSEEN "), nz_number),
-        StatusInfo::Unseen,
+        preceded(tag_no_case("HIGHESTMODSEQ "), mod_sequence_value),
+        StatusInfo::HighestModSeq,
     )(i)
 }
 
-// TODO! Incorrect
 // status-att-list = status-att SP number *(SP status-att SP number)
-// status-att = 'MESSAGES' | 'RECENT' | 'UIDNEXT' | 'UIDVALIDITY' | 'UNSEEN'
+// status-att = 'MESSAGES' | 'RECENT' | 'UIDNEXT' | 'UIDVALIDITY' | 'UNSEEN' |
+//              'HIGHESTMODSEQ'
 pub(crate) fn status_att_list(i: &[u8]) -> IResult<&[u8], Vec<StatusInfo>> {
-    many1(alt((
-        status_messages,
-        status_recent,
-        status_uidnext,
-        status_uidvalidity,
-        status_unseen,
-    )))(i)
+    separated_list1(
+        tag(" "),
+        alt((
+            status_messages,
+            status_recent,
+            status_uidnext,
+            status_uidvalidity,
+            status_unseen,
+            status_highestmodseq,
+        )),
+    )(i)
 }
 
 //mailbox = 'INBOX' | astring
-pub(crate) fn mailbox(i: &[u8]) -> IResult<&[u8], &str> {
+pub(crate) fn mailbox(i: &[u8]) -> IResult<&[u8], Cow<'_, str>> {
     astring(i)
 }
 
@@ -502,14 +667,13 @@ pub(crate) fn flag_keyword(i: &[u8]) -> IResult<&[u8], &str> {
 // flag-extension = '\' atom;
 // Future expansion
 pub(crate) fn flag_extension(i: &[u8]) -> IResult<&[u8], &str> {
-    // TODO: incorrect removing suffix '\'
-    map(tuple((tag("\\"), atom)), |(_, result)| result)(i)
+    map_res(recognize(tuple((tag("\\"), atom))), std::str::from_utf8)(i)
 }
 
 // TODO: Change return type because Flag must not contain Perm
 // flag-perm = flag | '\*'
 pub(crate) fn flag_perm(i: &[u8]) -> IResult<&[u8], Flag<'_>> {
-    map(flag, Flag::from)(i)
+    alt((value(Flag::Perm, tag("\\*")), map(flag, Flag::from)))(i)
 }
 
 // flag = '\Answered' | '\Flagged' | '\Deleted' | '\Seen' | '\Draft' | flag_keyword | flag_extension
@@ -520,10 +684,45 @@ pub(crate) fn flag(i: &[u8]) -> IResult<&[u8], &str> {
 
 // auth-type = atom
 pub(crate) fn auth_type(i: &[u8]) -> IResult<&[u8], &str> {
-    // TODO: Create enum for auth type
     atom(i)
 }
 
+// 'VANISHED' [SP '(EARLIER)'] SP known-uids, RFC 7162
+// known-uids = sequence-set
+pub(crate) fn vanished(i: &[u8]) -> IResult<&[u8], (bool, SequenceSet)> {
+    preceded(
+        tag_no_case("VANISHED "),
+        tuple((
+            map(opt(tag_no_case("(EARLIER) ")), |v| v.is_some()),
+            sequence_set,
+        )),
+    )(i)
+}
+
+// enable-data = "ENABLED" *(SP capability), RFC 5161
+pub(crate) fn enable_data(i: &[u8]) -> IResult<&[u8], Vec<Capability<'_>>> {
+    preceded(
+        tag_no_case("ENABLED"),
+        many0(preceded(tag(" "), capability)),
+    )(i)
+}
+
+// 'ID' SP id-params-list, RFC 2971
+// id-params-list = '(' #(string SP nstring) ')' | nil
+pub(crate) fn id_response(i: &[u8]) -> IResult<&[u8], IdParams<'_>> {
+    preceded(
+        tag_no_case("ID "),
+        alt((
+            delimited(
+                tag("("),
+                separated_list0(tag(" "), separated_pair(string, tag(" "), nstring)),
+                tag(")"),
+            ),
+            map(tag_no_case("NIL"), |_| Vec::new()),
+        )),
+    )(i)
+}
+
 // capability-data = "CAPABILITY" *(SP CAPABILITY) SP "IMAP4rev1" *(SP capability)
 pub(crate) fn capability_data(i: &[u8]) -> IResult<&[u8], Vec<Capability<'_>>> {
     // Grammar is not exactly as in rfc3501.
@@ -617,7 +816,7 @@ pub(crate) fn rtc_try_create(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
 // 'UIDNEXT' SP nz-number
 pub(crate) fn rtc_uidnext(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
     map(
-        preceded(tag_no_case("UIDNEXT "), nz_number),
+        preceded(tag_no_case("UIDNEXT "), number),
         RespTextCode::UidNext,
     )(i)
 }
@@ -625,7 +824,7 @@ pub(crate) fn rtc_uidnext(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
 // 'UIDVALIDITY' SP nz-number
 pub(crate) fn rtc_uidvalidity(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
     map(
-        preceded(tag_no_case("UIDVALIDITY "), nz_number),
+        preceded(tag_no_case("UIDVALIDITY "), number),
         RespTextCode::UidValidity,
     )(i)
 }
@@ -633,11 +832,70 @@ pub(crate) fn rtc_uidvalidity(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
 // 'UNSEEN' SP nz-number
 pub(crate) fn rtc_unseen(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
     map(
-        preceded(tag_no_case("UNSEEN "), nz_number),
+        preceded(tag_no_case("UNSEEN "), number),
         RespTextCode::Unseen,
     )(i)
 }
 
+// 'APPENDUID' SP nz-number SP append-uid, RFC 4315
+// append-uid = uniqueid | uid-set
+pub(crate) fn rtc_append_uid(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
+    map(
+        preceded(
+            tag_no_case("APPENDUID "),
+            separated_pair(nz_number, tag(" "), sequence_set),
+        ),
+        |(uid_validity, uids)| RespTextCode::AppendUid(uid_validity, uids),
+    )(i)
+}
+
+// 'COPYUID' SP nz-number SP uid-set SP uid-set, RFC 4315
+pub(crate) fn rtc_copy_uid(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
+    map(
+        preceded(
+            tag_no_case("COPYUID "),
+            tuple((nz_number, tag(" "), uid_set, tag(" "), uid_set)),
+        ),
+        |(uid_validity, _, source, _, destination)| {
+            RespTextCode::CopyUid(uid_validity, source, destination)
+        },
+    )(i)
+}
+
+// 'HIGHESTMODSEQ' SP mod-sequence-value, RFC 7162
+pub(crate) fn rtc_highest_modseq(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
+    map(
+        preceded(tag_no_case("HIGHESTMODSEQ "), mod_sequence_value),
+        RespTextCode::HighestModSeq,
+    )(i)
+}
+
+// 'NOMODSEQ', RFC 7162
+pub(crate) fn rtc_no_modseq(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
+    map(tag_no_case("NOMODSEQ"), |_| RespTextCode::NoModSeq)(i)
+}
+
+// 'MODIFIED' SP sequence-set, RFC 7162
+pub(crate) fn rtc_modified(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
+    map(
+        preceded(tag_no_case("MODIFIED "), sequence_set),
+        RespTextCode::Modified,
+    )(i)
+}
+
+// 'UIDNOTSTICKY', RFC 4315
+pub(crate) fn rtc_uid_not_sticky(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
+    map(tag_no_case("UIDNOTSTICKY"), |_| RespTextCode::UidNotSticky)(i)
+}
+
+// atom [SP 1*<any TEXT-CHAR except ']'>]
+pub(crate) fn rtc_other(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
+    map(
+        tuple((atom, opt(preceded(tag(" "), code_text)))),
+        |(name, text)| RespTextCode::Other(name, text),
+    )(i)
+}
+
 // resp-text-code = "ALERT" | "BADCHARSET" [SP "(" astring *(SP astring) ")" ] |
 //                  capability-data | "PARSE" | "PERMANENTFLAGS" SP "("
 //                  [ flag-perm *(SP flag-perm)] ")" | "READ-ONLY" |
@@ -657,15 +915,32 @@ pub(crate) fn resp_text_code(i: &[u8]) -> IResult<&[u8], RespTextCode<'_>> {
         rtc_uidnext,
         rtc_uidvalidity,
         rtc_unseen,
-        // TODO: add last branch from rfc3501 resp-text-code
+        rtc_append_uid,
+        rtc_copy_uid,
+        rtc_uid_not_sticky,
+        rtc_highest_modseq,
+        rtc_no_modseq,
+        rtc_modified,
+        // Unknown codes are kept as is
+        rtc_other,
     ))(i)
 }
 
 // resp-text = [ "[" resp-text-code "]" SP ] text
+// Some servers send the code without the text
 pub(crate) fn resp_text(i: &[u8]) -> IResult<&[u8], RespText<'_>> {
     map(
-        tuple((many0(delimited(tag("["), resp_text_code, tag("] "))), text)),
-        |(code, text)| RespText { code, text },
+        tuple((
+            many0(terminated(
+                delimited(tag("["), resp_text_code, tag("]")),
+                opt(tag(" ")),
+            )),
+            opt(text),
+        )),
+        |(code, text)| RespText {
+            code,
+            text: text.unwrap_or_default(),
+        },
     )(i)
 }
 
@@ -4,15 +4,18 @@ pub(crate) mod grammar;
 
 use self::core::base64;
 use crate::parser::types::{
-    ContinueReq, Greeting, GreetingStatus, TaggedResponse, UntaggedResponse,
+    ContinueReq, Greeting, GreetingStatus, RespText, TaggedResponse, UntaggedResponse,
+};
+use grammar::{
+    capability_data, enable_data, id_response, imap_tag, mailbox_data, message_data,
+    resp_cond_auth, resp_cond_bye, resp_cond_state, resp_text, vanished,
 };
-use grammar::{imap_tag, mailbox_data, resp_cond_auth, resp_cond_bye, resp_cond_state, resp_text};
 use nom::{
     branch::alt,
     bytes::streaming::tag,
     character::streaming::crlf,
-    combinator::map,
-    sequence::{delimited, tuple},
+    combinator::{map, opt, peek},
+    sequence::{delimited, preceded, terminated, tuple},
     IResult,
 };
 
@@ -38,12 +41,25 @@ pub(crate) fn greeting(i: &[u8]) -> IResult<&[u8], Greeting<'_>> {
 }
 
 // continue-req = '+' SP (resp-text | base64) CRLF
+// Many servers send an empty text, with or without SP.
+// Text which is valid base64 is taken as base64 challenge
 pub(crate) fn continue_req(i: &[u8]) -> IResult<&[u8], ContinueReq<'_>> {
     delimited(
-        tag("+ "),
+        tag("+"),
         alt((
-            map(resp_text, ContinueReq::Text),
-            map(base64, ContinueReq::Base64),
+            preceded(
+                tag(" "),
+                alt((
+                    map(terminated(base64, peek(crlf)), ContinueReq::Base64),
+                    map(resp_text, ContinueReq::Text),
+                )),
+            ),
+            map(opt(tag(" ")), |_| {
+                ContinueReq::Text(RespText {
+                    code: vec![],
+                    text: "",
+                })
+            }),
         )),
         crlf,
     )(i)
@@ -66,6 +82,13 @@ pub(crate) fn response_data(i: &[u8]) -> IResult<&[u8], UntaggedResponse<'_>> {
             map(resp_cond_state, UntaggedResponse::RespCond),
             map(resp_cond_bye, UntaggedResponse::RespBye),
             map(mailbox_data, UntaggedResponse::MailBox),
+            map(message_data, UntaggedResponse::Message),
+            map(capability_data, UntaggedResponse::Capability),
+            map(vanished, |(earlier, uids)| {
+                UntaggedResponse::Vanished(earlier, uids)
+            }),
+            map(enable_data, UntaggedResponse::Enabled),
+            map(id_response, UntaggedResponse::Id),
         )),
         crlf,
     )(i)
@@ -0,0 +1,157 @@
+use crate::parser::{
+    rfc3501::body::body,
+    types::{
+        Body, BodyDisposition, BodyEnc, BodyExt1Part, BodyExtMpart, BodyExtension, BodyFields,
+        BodyTypeBasic, BodyTypeMpart, BodyTypeMsg, BodyTypeText, Envelope, MediaBasic, MediaType,
+    },
+};
+
+fn fields<'a>(param: Vec<(&'a str, &'a str)>, enc: BodyEnc<'a>, octets: u32) -> BodyFields<'a> {
+    BodyFields {
+        param: Some(
+            param
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        ),
+        id: None,
+        desc: None,
+        enc,
+        octets,
+    }
+}
+
+fn plain(octets: u32, lines: u32) -> Body<'static> {
+    Body::Text(BodyTypeText {
+        subtype: "PLAIN".into(),
+        fields: fields(vec![("CHARSET", "US-ASCII")], BodyEnc::N7bit, octets),
+        lines,
+        ext: None,
+    })
+}
+
+#[test]
+fn parse_multipart() {
+    let input = b"((\"TEXT\" \"PLAIN\" (\"CHARSET\" \"US-ASCII\") NIL NIL \"7BIT\" 1152 23)\
+                  (\"TEXT\" \"PLAIN\" (\"CHARSET\" \"US-ASCII\") NIL NIL \"7BIT\" 4554 73) \
+                  \"MIXED\")";
+
+    let (rest, result) = body(input).unwrap();
+
+    let expected = Body::Multipart(BodyTypeMpart {
+        bodies: vec![plain(1152, 23), plain(4554, 73)],
+        subtype: "MIXED".into(),
+        ext: None,
+    });
+
+    assert!(rest.is_empty());
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn parse_multipart_extension_data() {
+    let input = b"((\"TEXT\" \"PLAIN\" (\"CHARSET\" \"US-ASCII\") NIL NIL \"7BIT\" 10 1 \
+                  \"Q2hlY2sgSW50ZWdyaXR5IQ==\" NIL NIL NIL)\
+                  (\"APPLICATION\" \"PDF\" (\"NAME\" \"a.pdf\") NIL NIL \"BASE64\" 2000 NIL \
+                  (\"ATTACHMENT\" (\"FILENAME\" \"a.pdf\")) (\"EN\" \"DE\") \"http://x\" \
+                  42 (1 \"a\" (NIL))) \
+                  \"MIXED\" (\"BOUNDARY\" \"xyz\") NIL \"EN\")";
+
+    let (rest, result) = body(input).unwrap();
+
+    let text = Body::Text(BodyTypeText {
+        subtype: "PLAIN".into(),
+        fields: fields(vec![("CHARSET", "US-ASCII")], BodyEnc::N7bit, 10),
+        lines: 1,
+        ext: Some(BodyExt1Part {
+            md5: Some("Q2hlY2sgSW50ZWdyaXR5IQ==".into()),
+            dsp: None,
+            lang: None,
+            loc: None,
+            extension: vec![],
+        }),
+    });
+
+    let pdf = Body::Basic(BodyTypeBasic {
+        media: MediaBasic {
+            media_type: MediaType::Application,
+            subtype: "PDF".into(),
+        },
+        fields: fields(vec![("NAME", "a.pdf")], BodyEnc::Base64, 2000),
+        ext: Some(BodyExt1Part {
+            md5: None,
+            dsp: Some(BodyDisposition {
+                kind: "ATTACHMENT".into(),
+                param: Some(vec![("FILENAME".into(), "a.pdf".into())]),
+            }),
+            lang: Some(vec!["EN".into(), "DE".into()]),
+            loc: Some("http://x".into()),
+            extension: vec![
+                BodyExtension::Number(42),
+                BodyExtension::List(vec![
+                    BodyExtension::Number(1),
+                    BodyExtension::NString(Some("a".into())),
+                    BodyExtension::List(vec![BodyExtension::NString(None)]),
+                ]),
+            ],
+        }),
+    });
+
+    let expected = Body::Multipart(BodyTypeMpart {
+        bodies: vec![text, pdf],
+        subtype: "MIXED".into(),
+        ext: Some(BodyExtMpart {
+            param: Some(vec![("BOUNDARY".into(), "xyz".into())]),
+            dsp: None,
+            lang: Some(vec!["EN".into()]),
+            loc: None,
+            extension: vec![],
+        }),
+    });
+
+    assert!(rest.is_empty());
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn parse_nested_message() {
+    let input = b"(\"MESSAGE\" \"RFC822\" NIL NIL NIL \"7BIT\" 300 \
+                  (NIL \"Hi\" NIL NIL NIL NIL NIL NIL NIL NIL) \
+                  ((\"TEXT\" \"PLAIN\" (\"CHARSET\" \"US-ASCII\") NIL NIL \"7BIT\" 100 4)\
+                  (\"TEXT\" \"PLAIN\" (\"CHARSET\" \"US-ASCII\") NIL NIL \"7BIT\" 50 2) \
+                  \"ALTERNATIVE\") 12)";
+
+    let (rest, result) = body(input).unwrap();
+
+    let expected = Body::Msg(BodyTypeMsg {
+        fields: BodyFields {
+            param: None,
+            id: None,
+            desc: None,
+            enc: BodyEnc::N7bit,
+            octets: 300,
+        },
+        envelope: Envelope {
+            date: None,
+            subject: Some("Hi".into()),
+            from: None,
+            sender: None,
+            reply_to: None,
+            to: None,
+            cc: None,
+            bcc: None,
+            in_reply_to: None,
+            message_id: None,
+        },
+        body: Box::new(Body::Multipart(BodyTypeMpart {
+            bodies: vec![plain(100, 4), plain(50, 2)],
+            subtype: "ALTERNATIVE".into(),
+            ext: None,
+        })),
+        lines: 12,
+        ext: None,
+    });
+
+    assert!(rest.is_empty());
+    assert_eq!(result, expected);
+}
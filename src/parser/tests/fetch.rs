@@ -0,0 +1,199 @@
+use super::assert_eq;
+use crate::parser::{
+    parse_response,
+    types::{
+        Address, Body, BodyEnc, BodyFields, BodyTypeText, DateTime, DefinedFlag, Envelope, Flag,
+        ImapResponse, MessageData, Month, MsgAtt, MsgFlag, Section, SectionText, Time,
+        UntaggedResponse,
+    },
+};
+use std::borrow::Cow;
+
+fn message(data: MessageData<'_>) -> ImapResponse<'_> {
+    ImapResponse::Data(UntaggedResponse::Message(data))
+}
+
+#[test]
+fn parse_expunge() {
+    let response = b"* 22 EXPUNGE\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    assert_eq(result, message(MessageData::Expunge(22)));
+}
+
+#[test]
+fn parse_fetch_flags_and_uid() {
+    let response = b"* 12 FETCH (FLAGS (\\Seen $Junk) UID 4827313)\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let fetch = MessageData::Fetch(
+        12,
+        vec![
+            MsgAtt::Flags(vec![
+                MsgFlag::Common(Flag::Defined(DefinedFlag::Seen)),
+                MsgFlag::Common(Flag::Keyword("$Junk")),
+            ]),
+            MsgAtt::Uid(4827313),
+        ],
+    );
+
+    assert_eq(result, message(fetch));
+}
+
+#[test]
+fn parse_fetch_modseq() {
+    let response = b"* 4 FETCH (UID 8 MODSEQ (12121231000) FLAGS (\\Deleted))\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let fetch = MessageData::Fetch(
+        4,
+        vec![
+            MsgAtt::Uid(8),
+            MsgAtt::ModSeq(12121231000),
+            MsgAtt::Flags(vec![MsgFlag::Common(Flag::Defined(DefinedFlag::Deleted))]),
+        ],
+    );
+
+    assert_eq(result, message(fetch));
+}
+
+#[test]
+fn parse_fetch_empty_flags() {
+    let response = b"* 1 FETCH (FLAGS ())\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    assert_eq(
+        result,
+        message(MessageData::Fetch(1, vec![MsgAtt::Flags(vec![])])),
+    );
+}
+
+#[test]
+fn parse_fetch_body_sections() {
+    let response = b"* 3 FETCH (BODY[HEADER.FIELDS (FROM \"SUBJECT\")] {9}\r\nFrom: a\r\n \
+                     BODY[1.2.MIME]<0> NIL BODY[] \"x \\\"y\\\"\")\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let fetch = MessageData::Fetch(
+        3,
+        vec![
+            MsgAtt::BodySection {
+                section: Section {
+                    part: vec![],
+                    text: Some(SectionText::HeaderFields(vec![
+                        "FROM".into(),
+                        "SUBJECT".into(),
+                    ])),
+                },
+                origin: None,
+                data: Some(b"From: a\r\n"),
+            },
+            MsgAtt::BodySection {
+                section: Section {
+                    part: vec![1, 2],
+                    text: Some(SectionText::Mime),
+                },
+                origin: Some(0),
+                data: None,
+            },
+            MsgAtt::BodySection {
+                section: Section::default(),
+                origin: None,
+                data: Some(b"x \\\"y\\\""),
+            },
+        ],
+    );
+
+    assert_eq(result, message(fetch));
+}
+
+#[test]
+fn parse_fetch_envelope() {
+    let response = b"* 12 FETCH (RFC822.SIZE 4286 INTERNALDATE \"17-Jul-1996 02:44:25 -0700\" \
+                     ENVELOPE (\"Wed, 17 Jul 1996 02:23:25 -0700 (PDT)\" \
+                     \"IMAP4rev1 WG mtg summary and minutes\" \
+                     ((\"Terry Gray\" NIL \"gray\" \"cac.washington.edu\")) NIL NIL \
+                     ((NIL NIL \"imap\" \"cac.washington.edu\")) \
+                     ((NIL NIL \"minutes\" \"CNRI.Reston.VA.US\")\
+                     (\"John Klensin\" NIL \"KLENSIN\" \"MIT.EDU\")) NIL NIL \
+                     \"<B27397-0100000@cac.washington.edu>\"))\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let address = |name: Option<&'static str>, mailbox: &'static str, host: &'static str| Address {
+        name: name.map(Cow::Borrowed),
+        adl: None,
+        mailbox: Some(mailbox.into()),
+        host: Some(host.into()),
+    };
+
+    let fetch = MessageData::Fetch(
+        12,
+        vec![
+            MsgAtt::Rfc822Size(4286),
+            MsgAtt::InternalDate(DateTime {
+                day: 17,
+                month: Month::Jul,
+                year: 1996,
+                time: Time {
+                    hours: 2,
+                    minutes: 44,
+                    seconds: 25,
+                },
+                zone: -700,
+            }),
+            MsgAtt::Envelope(Envelope {
+                date: Some("Wed, 17 Jul 1996 02:23:25 -0700 (PDT)".into()),
+                subject: Some("IMAP4rev1 WG mtg summary and minutes".into()),
+                from: Some(vec![address(
+                    Some("Terry Gray"),
+                    "gray",
+                    "cac.washington.edu",
+                )]),
+                sender: None,
+                reply_to: None,
+                to: Some(vec![address(None, "imap", "cac.washington.edu")]),
+                cc: Some(vec![
+                    address(None, "minutes", "CNRI.Reston.VA.US"),
+                    address(Some("John Klensin"), "KLENSIN", "MIT.EDU"),
+                ]),
+                bcc: None,
+                in_reply_to: None,
+                message_id: Some("<B27397-0100000@cac.washington.edu>".into()),
+            }),
+        ],
+    );
+
+    assert_eq(result, message(fetch));
+}
+
+#[test]
+fn parse_fetch_bodystructure() {
+    let response = b"* 1 FETCH (BODYSTRUCTURE (\"TEXT\" \"PLAIN\" (\"CHARSET\" \"US-ASCII\") \
+                     NIL NIL \"7BIT\" 3028 92))\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let fetch = MessageData::Fetch(
+        1,
+        vec![MsgAtt::BodyStructure(Body::Text(BodyTypeText {
+            subtype: "PLAIN".into(),
+            fields: BodyFields {
+                param: Some(vec![("CHARSET".into(), "US-ASCII".into())]),
+                id: None,
+                desc: None,
+                enc: BodyEnc::N7bit,
+                octets: 3028,
+            },
+            lines: 92,
+            ext: None,
+        }))],
+    );
+
+    assert_eq(result, message(fetch));
+}
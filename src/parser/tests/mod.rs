@@ -1,9 +1,13 @@
+mod body;
+mod fetch;
+
 use crate::{
     parser::{
-        parse,
+        parse, parse_response,
         types::{
-            ContinueReq, Greeting, GreetingStatus, ImapResponse, ImapResult, RespCond, RespText,
-            TaggedResponse,
+            Capability, ContinueReq, DefinedFlag, Flag, Greeting, GreetingStatus, ImapResponse,
+            ImapResult, ListDefinedFlag, ListFlag, ListMailBox, MailBoxData, RespCond, RespText,
+            RespTextCode, StatusInfo, StatusResponse, TaggedResponse, UntaggedResponse,
         },
     },
     tag::Tag,
@@ -61,3 +65,291 @@ fn parse_continue_req() {
 
     assert_eq(result, continue_req);
 }
+
+#[test]
+fn parse_base64_continue_req() {
+    for (response, challenge) in [
+        (&b"+ VXNlcm5hbWU6\r\n"[..], "VXNlcm5hbWU6"),
+        (&b"+ dGVzdA==\r\n"[..], "dGVzdA=="),
+        (&b"+ dGU=\r\n"[..], "dGU="),
+    ] {
+        let result = parse(response).unwrap();
+
+        assert_eq(
+            result,
+            ImapResponse::Continue(ContinueReq::Base64(challenge)),
+        );
+    }
+}
+
+#[test]
+fn parse_empty_continue_req() {
+    for response in [&b"+ \r\n"[..], &b"+\r\n"[..]] {
+        let result = parse(response).unwrap();
+
+        assert_eq(
+            result,
+            ImapResponse::Continue(ContinueReq::Text(resp_text(""))),
+        );
+    }
+}
+
+#[test]
+fn parse_untagged_flags() {
+    let response = b"* FLAGS (\\Seen \\Answered $Forwarded)\r\n";
+
+    let result = parse(response).unwrap();
+
+    let flags = ImapResponse::Data(UntaggedResponse::MailBox(MailBoxData::Flags(vec![
+        Flag::Defined(DefinedFlag::Seen),
+        Flag::Defined(DefinedFlag::Answered),
+        Flag::Keyword("$Forwarded"),
+    ])));
+
+    assert_eq(result, flags);
+}
+
+#[test]
+fn parse_untagged_search() {
+    for (response, numbers, modseq) in [
+        (&b"* SEARCH 2 84 882\r\n"[..], vec![2, 84, 882], None),
+        (&b"* SEARCH\r\n"[..], vec![], None),
+        (&b"* SEARCH 7 \r\n"[..], vec![7], None),
+        (
+            &b"* SEARCH 2 5 6 (MODSEQ 917162500)\r\n"[..],
+            vec![2, 5, 6],
+            Some(917162500),
+        ),
+    ] {
+        let result = parse(response).unwrap();
+
+        let search = ImapResponse::Data(UntaggedResponse::MailBox(MailBoxData::Search(
+            numbers, modseq,
+        )));
+
+        assert_eq(result, search);
+    }
+}
+
+#[test]
+fn parse_untagged_list() {
+    let response = b"* LIST (\\Noselect \\HasChildren) \"/\" \"~/Mail/foo\"\r\n";
+
+    let result = parse(response).unwrap();
+
+    let list = ImapResponse::Data(UntaggedResponse::MailBox(MailBoxData::List(ListMailBox {
+        flags: vec![
+            ListFlag::Defined(ListDefinedFlag::Noselect),
+            ListFlag::Extension("\\HasChildren"),
+        ],
+        delimiter: Some("/".into()),
+        name: "~/Mail/foo".into(),
+    })));
+
+    assert_eq(result, list);
+}
+
+#[test]
+fn parse_untagged_status() {
+    let response =
+        b"* STATUS blurdybloop (MESSAGES 231 UIDNEXT 44292 UNSEEN 0 HIGHESTMODSEQ 7011231777)\r\n";
+
+    let result = parse(response).unwrap();
+
+    let status = ImapResponse::Data(UntaggedResponse::MailBox(MailBoxData::Status(
+        StatusResponse {
+            name: "blurdybloop".into(),
+            status: vec![
+                StatusInfo::Messages(231),
+                StatusInfo::UidNext(44292),
+                StatusInfo::Unseen(0),
+                StatusInfo::HighestModSeq(7011231777),
+            ],
+        },
+    )));
+
+    assert_eq(result, status);
+}
+
+#[test]
+fn parse_untagged_ok_after_greeting() {
+    let response = b"* OK [PERMANENTFLAGS (\\Deleted \\*)] Limited\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let ok = ImapResponse::Data(UntaggedResponse::RespCond(RespCond {
+        status: ImapResult::Ok,
+        text: RespText {
+            code: vec![RespTextCode::PermanentFlags(vec![
+                Flag::Defined(DefinedFlag::Deleted),
+                Flag::Perm,
+            ])],
+            text: "Limited",
+        },
+    }));
+
+    assert_eq(result, ok);
+}
+
+#[test]
+fn parse_uidplus_codes() {
+    let response = b"a1 OK [COPYUID 38505 304,319:320 3956:3958] Done\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let done = ImapResponse::Response(TaggedResponse {
+        tag: Tag::new('a', 1),
+        resp: RespCond {
+            status: ImapResult::Ok,
+            text: RespText {
+                code: vec![RespTextCode::CopyUid(
+                    38505,
+                    vec![304, 319, 320],
+                    vec![3956, 3957, 3958],
+                )],
+                text: "Done",
+            },
+        },
+    });
+
+    assert_eq(result, done);
+
+    let response = b"* NO [UIDNOTSTICKY] [X-NEWCODE some text]\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let no = ImapResponse::Data(UntaggedResponse::RespCond(RespCond {
+        status: ImapResult::No,
+        text: RespText {
+            code: vec![
+                RespTextCode::UidNotSticky,
+                RespTextCode::Other("X-NEWCODE", Some("some text")),
+            ],
+            text: "",
+        },
+    }));
+
+    assert_eq(result, no);
+}
+
+#[test]
+fn parse_copy_uid_order() {
+    let code = |response: &'static [u8]| match parse_response(response).unwrap() {
+        (_, ImapResponse::Response(mut done)) => done.resp.text.code.remove(0),
+        (_, v) => panic!("Unexpected response {:?}", v),
+    };
+
+    // Identifiers are mapped by position, so the order is kept
+    assert_eq!(
+        code(b"a1 OK [COPYUID 1 3,1 10:11] Done\r\n"),
+        RespTextCode::CopyUid(1, vec![3, 1], vec![10, 11])
+    );
+    assert_eq!(
+        code(b"a1 OK [COPYUID 1 4:2 7,5:6] Done\r\n"),
+        RespTextCode::CopyUid(1, vec![4, 3, 2], vec![7, 5, 6])
+    );
+    // `*` is not a unique identifier
+    assert!(!matches!(
+        code(b"a1 OK [COPYUID 1 2:* 10:11] Done\r\n"),
+        RespTextCode::CopyUid(..)
+    ));
+}
+
+#[test]
+fn parse_condstore_codes() {
+    let response = b"* OK [HIGHESTMODSEQ 715194045007] Highest\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let ok = ImapResponse::Data(UntaggedResponse::RespCond(RespCond {
+        status: ImapResult::Ok,
+        text: RespText {
+            code: vec![RespTextCode::HighestModSeq(715194045007)],
+            text: "Highest",
+        },
+    }));
+
+    assert_eq(result, ok);
+
+    let response = b"a1 OK [MODIFIED 7,9] Conditional STORE failed\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let done = ImapResponse::Response(TaggedResponse {
+        tag: Tag::new('a', 1),
+        resp: RespCond {
+            status: ImapResult::Ok,
+            text: RespText {
+                code: vec![RespTextCode::Modified("7,9".parse().unwrap())],
+                text: "Conditional STORE failed",
+            },
+        },
+    });
+
+    assert_eq(result, done);
+}
+
+#[test]
+fn parse_untagged_vanished() {
+    let response = b"* VANISHED (EARLIER) 41,43:116,118\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let vanished = ImapResponse::Data(UntaggedResponse::Vanished(
+        true,
+        "41,43:116,118".parse().unwrap(),
+    ));
+
+    assert_eq(result, vanished);
+
+    let response = b"* VANISHED 405,407\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let vanished = ImapResponse::Data(UntaggedResponse::Vanished(
+        false,
+        "405,407".parse().unwrap(),
+    ));
+
+    assert_eq(result, vanished);
+}
+
+#[test]
+fn parse_untagged_enabled() {
+    let response = b"* ENABLED CONDSTORE QRESYNC\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let enabled = ImapResponse::Data(UntaggedResponse::Enabled(vec![
+        Capability::Other("CONDSTORE"),
+        Capability::Other("QRESYNC"),
+    ]));
+
+    assert_eq(result, enabled);
+
+    let result = parse_response(b"* ENABLED\r\n").unwrap();
+
+    assert_eq(
+        result,
+        ImapResponse::Data(UntaggedResponse::Enabled(vec![])),
+    );
+}
+
+#[test]
+fn parse_untagged_id() {
+    let response = b"* ID (\"name\" \"Cyrus\" \"version\" \"1.5\" \"support-url\" NIL)\r\n";
+
+    let result = parse_response(response).unwrap();
+
+    let id = ImapResponse::Data(UntaggedResponse::Id(vec![
+        ("name".into(), Some("Cyrus".into())),
+        ("version".into(), Some("1.5".into())),
+        ("support-url".into(), None),
+    ]));
+
+    assert_eq(result, id);
+
+    let result = parse_response(b"* ID NIL\r\n").unwrap();
+
+    assert_eq(result, ImapResponse::Data(UntaggedResponse::Id(vec![])));
+}
@@ -1,7 +1,8 @@
-//!
+//! Zero-copy types produced by the parser
 
 use crate::tag::Tag;
-use std::convert::TryFrom;
+use crate::types::SequenceSet;
+use std::{borrow::Cow, convert::TryFrom, fmt::Display};
 
 use crate::error::{create_custom_error, Error};
 
@@ -10,6 +11,7 @@ pub(crate) enum ImapResponse<'a> {
     Greeting(Greeting<'a>),
     Continue(ContinueReq<'a>),
     Response(TaggedResponse<'a>),
+    Data(UntaggedResponse<'a>),
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -48,8 +50,18 @@ pub(crate) enum UntaggedResponse<'a> {
     RespCond(RespCond<'a>),
     RespBye(ByeResponse<'a>),
     MailBox(MailBoxData<'a>),
+    Message(MessageData<'a>),
+    Capability(Vec<Capability<'a>>),
+    // Expunged UIDs, EARLIER flag and the set, RFC 7162
+    Vanished(bool, SequenceSet),
+    // Extensions enabled by ENABLE, RFC 5161
+    Enabled(Vec<Capability<'a>>),
+    // Field and value pairs of the server, empty for NIL, RFC 2971
+    Id(IdParams<'a>),
 }
 
+pub(crate) type IdParams<'a> = Vec<(Cow<'a, str>, Option<Cow<'a, str>>)>;
+
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct RespCond<'a> {
     pub(crate) status: ImapResult,
@@ -61,40 +73,46 @@ pub(crate) struct ByeResponse<'a> {
     pub(crate) resp: RespText<'a>,
 }
 
-#[derive(Debug, Eq, PartialEq)]
-pub(crate) enum DefinedFlag {
+/// System flags defined by RFC 3501
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum DefinedFlag {
+    /// `\Seen`
     Seen,
+    /// `\Answered`
     Answered,
+    /// `\Flagged`
     Flagged,
+    /// `\Deleted`
     Deleted,
+    /// `\Draft`
     Draft,
+    /// `\Recent`, can not be changed by the client
     Recent,
 }
 
-// TODO: It is incorrect, because flags are case-insesitive
-// Add function for insesitive cmp
+// Flags are case-insensitive
 impl TryFrom<&str> for DefinedFlag {
     type Error = Error;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value {
-            "\\Seen" => Ok(Self::Seen),
-            "\\Answred" => Ok(Self::Answered),
-            "\\Flagged" => Ok(Self::Flagged),
-            "\\Deleted" => Ok(Self::Deleted),
-            "\\Draft" => Ok(Self::Draft),
-            "\\Recent" => Ok(Self::Recent),
-            _ => Err(create_custom_error(format!(
-                "Can not convert {} into DefinedFlag",
-                value
-            ))),
-        }
+        [
+            Self::Seen,
+            Self::Answered,
+            Self::Flagged,
+            Self::Deleted,
+            Self::Draft,
+            Self::Recent,
+        ]
+        .iter()
+        .find(|flag| flag.to_string().eq_ignore_ascii_case(value))
+        .copied()
+        .ok_or_else(|| create_custom_error(format!("Can not convert {} into DefinedFlag", value)))
     }
 }
 
 // TODO: Flag should be without Perm branch
 // Perm branch used only for resp_text_code
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub(crate) enum Flag<'a> {
     Defined(DefinedFlag),
     Keyword(&'a str),
@@ -118,30 +136,62 @@ impl<'a> From<&'a str> for Flag<'a> {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
-pub(crate) enum ListDefinedFlag {
+impl Display for DefinedFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Seen => "\\Seen",
+            Self::Answered => "\\Answered",
+            Self::Flagged => "\\Flagged",
+            Self::Deleted => "\\Deleted",
+            Self::Draft => "\\Draft",
+            Self::Recent => "\\Recent",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl Display for Flag<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Defined(v) => write!(f, "{}", v),
+            Self::Keyword(v) | Self::Extension(v) => write!(f, "{}", v),
+            Self::Perm => write!(f, "\\*"),
+        }
+    }
+}
+
+/// Mailbox name attributes defined by RFC 3501
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ListDefinedFlag {
+    /// `\Noinferiors`, child mailboxes can not be created
     Noinferiors,
+    /// `\Noselect`, the mailbox can not be selected
     Noselect,
+    /// `\Marked`
     Marked,
+    /// `\Unmarked`
     Unmarked,
 }
 
-// TODO: It is incorrect, because flags are case-insesitive
-// Add function for insesitive cmp
+// Flags are case-insensitive
 impl TryFrom<&str> for ListDefinedFlag {
     type Error = Error;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value {
-            "\\Noinferiors" => Ok(Self::Noinferiors),
-            "\\Noselect" => Ok(Self::Noselect),
-            "\\Marked" => Ok(Self::Marked),
-            "\\Unmarked" => Ok(Self::Unmarked),
-            _ => Err(create_custom_error(format!(
-                "Can not convert {} into ListDefinedFlag",
-                value
-            ))),
-        }
+        let flags = [
+            ("\\Noinferiors", Self::Noinferiors),
+            ("\\Noselect", Self::Noselect),
+            ("\\Marked", Self::Marked),
+            ("\\Unmarked", Self::Unmarked),
+        ];
+
+        flags
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(value))
+            .map(|(_, flag)| *flag)
+            .ok_or_else(|| {
+                create_custom_error(format!("Can not convert {} into ListDefinedFlag", value))
+            })
     }
 }
 
@@ -164,22 +214,21 @@ impl<'a> From<&'a str> for ListFlag<'a> {
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct ListMailBox<'a> {
     pub(crate) flags: Vec<ListFlag<'a>>,
-    pub(crate) delimiter: Option<&'a str>,
-    pub(crate) name: &'a str,
+    pub(crate) delimiter: Option<Cow<'a, str>>,
+    pub(crate) name: Cow<'a, str>,
 }
 
+// Names are typed by the owned model, see crate::types::Capability
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) enum Capability<'a> {
-    // TODO: Create enum for common auth types
     Auth(&'a str),
-    // TODO: Create enum for common other capabilities
     Other(&'a str),
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) enum RespTextCode<'a> {
     Alert,
-    BadCharset(Vec<&'a str>),
+    BadCharset(Vec<Cow<'a, str>>),
     Capability(Vec<Capability<'a>>),
     Parse,
     PermanentFlags(Vec<Flag<'a>>),
@@ -189,7 +238,16 @@ pub(crate) enum RespTextCode<'a> {
     UidNext(u32),
     UidValidity(u32),
     Unseen(u32),
-    // TODO: add last branch
+    // RFC 4315
+    AppendUid(u32, SequenceSet),
+    // Source and destination identifiers in order of the response
+    CopyUid(u32, Vec<u32>, Vec<u32>),
+    UidNotSticky,
+    // RFC 7162
+    HighestModSeq(u64),
+    NoModSeq,
+    Modified(SequenceSet),
+    Other(&'a str, Option<&'a str>),
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -198,18 +256,26 @@ pub(crate) struct RespText<'a> {
     pub(crate) text: &'a str,
 }
 
-#[derive(Debug, Eq, PartialEq)]
-pub(crate) enum StatusInfo {
+/// Item of the `STATUS` response
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum StatusInfo {
+    /// Number of messages in the mailbox
     Messages(u32),
+    /// Number of messages with the `\Recent` flag set
     Recent(u32),
+    /// Next unique identifier value
     UidNext(u32),
+    /// Unique identifier validity value
     UidValidity(u32),
+    /// Number of messages without the `\Seen` flag set
     Unseen(u32),
+    /// Highest mod-sequence of all messages, RFC 7162
+    HighestModSeq(u64),
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct StatusResponse<'a> {
-    pub(crate) name: &'a str,
+    pub(crate) name: Cow<'a, str>,
     pub(crate) status: Vec<StatusInfo>,
 }
 
@@ -218,7 +284,8 @@ pub(crate) enum MailBoxData<'a> {
     Flags(Vec<Flag<'a>>),
     List(ListMailBox<'a>),
     Lsub(ListMailBox<'a>),
-    Search(Vec<u32>),
+    // Matched messages and the highest mod-sequence of them, RFC 7162
+    Search(Vec<u32>, Option<u64>),
     Status(StatusResponse<'a>),
     Exists(u32),
     Recent(u32),
@@ -232,52 +299,82 @@ pub(crate) enum MsgFlag<'a> {
 
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct Address<'a> {
-    pub(crate) name: Option<&'a str>,
-    pub(crate) adl: Option<&'a str>,
-    pub(crate) mailbox: Option<&'a str>,
-    pub(crate) host: Option<&'a str>,
+    pub(crate) name: Option<Cow<'a, str>>,
+    pub(crate) adl: Option<Cow<'a, str>>,
+    pub(crate) mailbox: Option<Cow<'a, str>>,
+    pub(crate) host: Option<Cow<'a, str>>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct Envelope<'a> {
-    pub(crate) date: Option<&'a str>,
-    pub(crate) subject: Option<&'a str>,
+    pub(crate) date: Option<Cow<'a, str>>,
+    pub(crate) subject: Option<Cow<'a, str>>,
     pub(crate) from: Option<Vec<Address<'a>>>,
     pub(crate) sender: Option<Vec<Address<'a>>>,
     pub(crate) reply_to: Option<Vec<Address<'a>>>,
     pub(crate) to: Option<Vec<Address<'a>>>,
     pub(crate) cc: Option<Vec<Address<'a>>>,
     pub(crate) bcc: Option<Vec<Address<'a>>>,
-    pub(crate) in_reply_to: Option<&'a str>,
-    pub(crate) message_id: Option<&'a str>,
+    pub(crate) in_reply_to: Option<Cow<'a, str>>,
+    pub(crate) message_id: Option<Cow<'a, str>>,
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) enum MsgAtt<'a> {
     Envelope(Envelope<'a>),
     InternalDate(DateTime),
-    Rfc822(Option<&'a str>),
-    Rfc822Header(Option<&'a str>),
-    Rfc822Text(Option<&'a str>),
+    Rfc822(Option<&'a [u8]>),
+    Rfc822Header(Option<&'a [u8]>),
+    Rfc822Text(Option<&'a [u8]>),
     Rfc822Size(u32),
+    Body(Body<'a>),
+    BodyStructure(Body<'a>),
+    BodySection {
+        section: Section<'a>,
+        origin: Option<u32>,
+        data: Option<&'a [u8]>,
+    },
+    Uid(u32),
     Flags(Vec<MsgFlag<'a>>),
+    // RFC 7162
+    ModSeq(u64),
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Section<'a> {
+    // Empty for the whole message
+    pub(crate) part: Vec<u32>,
+    pub(crate) text: Option<SectionText<'a>>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum SectionText<'a> {
+    Header,
+    HeaderFields(Vec<Cow<'a, str>>),
+    HeaderFieldsNot(Vec<Cow<'a, str>>),
+    Text,
+    Mime,
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) enum MessageData<'a> {
     Expunge(u32),
-    Fetch(MsgAtt<'a>),
+    // Sequence number and attributes of the message
+    Fetch(u32, Vec<MsgAtt<'a>>),
 }
 
-#[derive(Debug, Eq, PartialEq)]
-pub(crate) struct Time {
-    pub(crate) hours: u8,
-    pub(crate) minutes: u8,
-    pub(crate) seconds: u8,
+/// Time of the day
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Time {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub(crate) enum Month {
+/// Month of the year
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Month {
     Jan,
     Feb,
     Mar,
@@ -292,52 +389,99 @@ pub(crate) enum Month {
     Dec,
 }
 
-#[derive(Debug, Eq, PartialEq)]
-pub(crate) struct DateTime {
-    pub(crate) day: u8,
-    pub(crate) month: Month,
-    pub(crate) year: u16,
-    pub(crate) time: Time,
-    pub(crate) zone: i16,
+/// Date and time as sent by the server, e.g. `INTERNALDATE`
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct DateTime {
+    /// Day of the month
+    pub day: u8,
+    pub month: Month,
+    pub year: u16,
+    pub time: Time,
+    /// Offset from UTC in `[+-]HHMM` form, e.g. `-700` for `-0700`
+    pub zone: i16,
+}
+
+impl Display for Month {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Jan => "Jan",
+            Self::Feb => "Feb",
+            Self::Mar => "Mar",
+            Self::Apr => "Apr",
+            Self::May => "May",
+            Self::Jun => "Jun",
+            Self::Jul => "Jul",
+            Self::Aug => "Aug",
+            Self::Sep => "Sep",
+            Self::Oct => "Oct",
+            Self::Nov => "Nov",
+            Self::Dec => "Dec",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// date-time without DQUOTEs, e.g. ` 7-Jul-1996 02:44:25 -0700`
+impl Display for DateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.zone < 0 { '-' } else { '+' };
+        write!(
+            f,
+            "{:>2}-{}-{:04} {:02}:{:02}:{:02} {}{:04}",
+            self.day,
+            self.month,
+            self.year,
+            self.time.hours,
+            self.time.minutes,
+            self.time.seconds,
+            sign,
+            self.zone.abs()
+        )
+    }
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) enum Body<'a> {
     Basic(BodyTypeBasic<'a>),
     Msg(BodyTypeMsg<'a>),
+    Text(BodyTypeText<'a>),
+    Multipart(BodyTypeMpart<'a>),
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub(crate) enum MediaType<'a> {
     Application,
     Audio,
     Image,
     Message,
     Video,
-    Custom(&'a str),
+    Custom(Cow<'a, str>),
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct MediaBasic<'a> {
     pub(crate) media_type: MediaType<'a>,
-    pub(crate) subtype: &'a str,
+    pub(crate) subtype: Cow<'a, str>,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub(crate) enum BodyEnc<'a> {
     N7bit,
     N8bit,
     Binary,
     Base64,
     QuotedPrintable,
-    Custom(&'a str),
+    Custom(Cow<'a, str>),
 }
 
+pub(crate) type BodyParams<'a> = Vec<(Cow<'a, str>, Cow<'a, str>)>;
+
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct BodyFields<'a> {
-    pub(crate) param: Option<Vec<(&'a str, &'a str)>>,
-    pub(crate) id: Option<&'a str>,
-    pub(crate) desc: Option<&'a str>,
+    pub(crate) param: Option<BodyParams<'a>>,
+    pub(crate) id: Option<Cow<'a, str>>,
+    pub(crate) desc: Option<Cow<'a, str>>,
     pub(crate) enc: BodyEnc<'a>,
     pub(crate) octets: u32,
 }
@@ -346,6 +490,7 @@ pub(crate) struct BodyFields<'a> {
 pub(crate) struct BodyTypeBasic<'a> {
     pub(crate) media: MediaBasic<'a>,
     pub(crate) fields: BodyFields<'a>,
+    pub(crate) ext: Option<BodyExt1Part<'a>>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -354,16 +499,53 @@ pub(crate) struct BodyTypeMsg<'a> {
     pub(crate) envelope: Envelope<'a>,
     pub(crate) body: Box<Body<'a>>,
     pub(crate) lines: u32,
+    pub(crate) ext: Option<BodyExt1Part<'a>>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct BodyTypeText<'a> {
-    pub(crate) subtype: &'a str,
+    pub(crate) subtype: Cow<'a, str>,
     pub(crate) fields: BodyFields<'a>,
     pub(crate) lines: u32,
+    pub(crate) ext: Option<BodyExt1Part<'a>>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct BodyTypeMpart<'a> {
+    pub(crate) bodies: Vec<Body<'a>>,
+    pub(crate) subtype: Cow<'a, str>,
+    pub(crate) ext: Option<BodyExtMpart<'a>>,
 }
 
+// Absent fields and NIL are not distinguished
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct BodyExt1Part<'a> {
-    md5: Option<&'a str>,
+    pub(crate) md5: Option<Cow<'a, str>>,
+    pub(crate) dsp: Option<BodyDisposition<'a>>,
+    pub(crate) lang: Option<Vec<Cow<'a, str>>>,
+    pub(crate) loc: Option<Cow<'a, str>>,
+    pub(crate) extension: Vec<BodyExtension<'a>>,
+}
+
+// Absent fields and NIL are not distinguished
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct BodyExtMpart<'a> {
+    pub(crate) param: Option<BodyParams<'a>>,
+    pub(crate) dsp: Option<BodyDisposition<'a>>,
+    pub(crate) lang: Option<Vec<Cow<'a, str>>>,
+    pub(crate) loc: Option<Cow<'a, str>>,
+    pub(crate) extension: Vec<BodyExtension<'a>>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct BodyDisposition<'a> {
+    pub(crate) kind: Cow<'a, str>,
+    pub(crate) param: Option<BodyParams<'a>>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum BodyExtension<'a> {
+    NString(Option<Cow<'a, str>>),
+    Number(u32),
+    List(Vec<BodyExtension<'a>>),
 }
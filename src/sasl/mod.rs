@@ -0,0 +1,204 @@
+//! SASL mechanisms for `AUTHENTICATE` command, RFC 4422
+
+mod scram;
+
+pub use scram::Scram;
+
+use crate::error::{Error, Result};
+
+/// SASL mechanism driving the challenge/response exchange of `AUTHENTICATE`.
+/// Challenges and responses are passed decoded, base64 is handled by the client
+pub trait Authenticator: Send {
+    /// Name of the mechanism as in `AUTH=<mechanism>` capability
+    fn mechanism(&self) -> &str;
+
+    /// Response of client-first mechanisms sent before any challenge.
+    /// It is sent along with the command if the server supports `SASL-IR`
+    fn initial_response(&mut self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Response to the challenge of the server
+    fn response(&mut self, challenge: &[u8]) -> Result<Vec<u8>>;
+
+    /// Called when the server completes the exchange with `OK`. Mechanisms
+    /// which authenticate the server fail here if it has not proved itself
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `PLAIN` mechanism, RFC 4616
+#[derive(Debug, Clone)]
+pub struct Plain {
+    username: String,
+    password: String,
+}
+
+impl Plain {
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        }
+    }
+}
+
+impl Authenticator for Plain {
+    fn mechanism(&self) -> &str {
+        "PLAIN"
+    }
+
+    fn initial_response(&mut self) -> Option<Vec<u8>> {
+        Some(format!("\0{}\0{}", self.username, self.password).into_bytes())
+    }
+
+    fn response(&mut self, _challenge: &[u8]) -> Result<Vec<u8>> {
+        Err(Error::Sasl("Unexpected challenge for PLAIN".to_owned()))
+    }
+}
+
+/// Obsolete `LOGIN` mechanism, the server asks for the username
+/// and then for the password
+#[derive(Debug, Clone)]
+pub struct Login {
+    username: String,
+    password: String,
+    step: usize,
+}
+
+impl Login {
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.to_owned(),
+            password: password.to_owned(),
+            step: 0,
+        }
+    }
+}
+
+impl Authenticator for Login {
+    fn mechanism(&self) -> &str {
+        "LOGIN"
+    }
+
+    fn response(&mut self, _challenge: &[u8]) -> Result<Vec<u8>> {
+        self.step += 1;
+        match self.step {
+            1 => Ok(self.username.as_bytes().to_vec()),
+            2 => Ok(self.password.as_bytes().to_vec()),
+            _ => Err(Error::Sasl("Unexpected challenge for LOGIN".to_owned())),
+        }
+    }
+}
+
+/// `XOAUTH2` mechanism used by Gmail and Outlook
+#[derive(Debug, Clone)]
+pub struct XOAuth2 {
+    username: String,
+    token: String,
+}
+
+impl XOAuth2 {
+    pub fn new(username: &str, token: &str) -> Self {
+        Self {
+            username: username.to_owned(),
+            token: token.to_owned(),
+        }
+    }
+}
+
+impl Authenticator for XOAuth2 {
+    fn mechanism(&self) -> &str {
+        "XOAUTH2"
+    }
+
+    fn initial_response(&mut self) -> Option<Vec<u8>> {
+        Some(
+            format!(
+                "user={}\x01auth=Bearer {}\x01\x01",
+                self.username, self.token
+            )
+            .into_bytes(),
+        )
+    }
+
+    /// The challenge is a JSON error, the server completes
+    /// the command with `NO` after the empty response
+    fn response(&mut self, _challenge: &[u8]) -> Result<Vec<u8>> {
+        Ok(vec![])
+    }
+}
+
+/// `OAUTHBEARER` mechanism, RFC 7628
+#[derive(Debug, Clone)]
+pub struct OAuthBearer {
+    username: String,
+    token: String,
+}
+
+impl OAuthBearer {
+    pub fn new(username: &str, token: &str) -> Self {
+        Self {
+            username: username.to_owned(),
+            token: token.to_owned(),
+        }
+    }
+}
+
+impl Authenticator for OAuthBearer {
+    fn mechanism(&self) -> &str {
+        "OAUTHBEARER"
+    }
+
+    fn initial_response(&mut self) -> Option<Vec<u8>> {
+        let username = saslname(&self.username);
+        Some(format!("n,a={},\x01auth=Bearer {}\x01\x01", username, self.token).into_bytes())
+    }
+
+    /// The challenge is a JSON error, the server completes
+    /// the command with `NO` after the dummy response
+    fn response(&mut self, _challenge: &[u8]) -> Result<Vec<u8>> {
+        Ok(vec![0x01])
+    }
+}
+
+// ',' and '=' in saslname are encoded as "=2C" and "=3D"
+fn saslname(username: &str) -> String {
+    username.replace('=', "=3D").replace(',', "=2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initial_responses() {
+        assert_eq!(
+            Plain::new("user", "pass").initial_response().unwrap(),
+            b"\0user\0pass"
+        );
+        assert_eq!(
+            XOAuth2::new("user@example.com", "token")
+                .initial_response()
+                .unwrap(),
+            b"user=user@example.com\x01auth=Bearer token\x01\x01"
+        );
+        assert_eq!(
+            OAuthBearer::new("a,b=c", "token")
+                .initial_response()
+                .unwrap(),
+            b"n,a=a=2Cb=3Dc,\x01auth=Bearer token\x01\x01"
+        );
+    }
+
+    #[test]
+    fn login_steps() {
+        let mut login = Login::new("user", "pass");
+
+        assert!(login.initial_response().is_none());
+        assert_eq!(login.response(b"Username:").unwrap(), b"user");
+        assert_eq!(login.response(b"Password:").unwrap(), b"pass");
+        assert!(login.response(b"").is_err());
+    }
+}
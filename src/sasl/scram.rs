@@ -0,0 +1,318 @@
+//! `SCRAM-SHA-1` and `SCRAM-SHA-256` mechanisms, RFC 5802 and RFC 7677
+
+use super::{saslname, Authenticator};
+use crate::error::{Error, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+// Random bytes of the client nonce, base64 encoded before sending
+const NONCE_LENGTH: usize = 24;
+
+#[derive(Debug, Clone, Copy)]
+enum Hash {
+    Sha1,
+    Sha256,
+}
+
+impl Hash {
+    fn hash(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => Sha1::digest(data).to_vec(),
+            Self::Sha256 => Sha256::digest(data).to_vec(),
+        }
+    }
+
+    fn hmac(self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        fn hmac<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+            let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts any key");
+            mac.update(data);
+            mac.finalize().into_bytes().to_vec()
+        }
+
+        match self {
+            Self::Sha1 => hmac::<Hmac<Sha1>>(key, data),
+            Self::Sha256 => hmac::<Hmac<Sha256>>(key, data),
+        }
+    }
+
+    // Hi() is PBKDF2 with HMAC as the pseudorandom function
+    fn hi(self, password: &[u8], salt: &[u8], iterations: u32) -> Vec<u8> {
+        match self {
+            Self::Sha1 => {
+                let mut salted = [0; 20];
+                pbkdf2::pbkdf2_hmac::<Sha1>(password, salt, iterations, &mut salted);
+                salted.to_vec()
+            }
+            Self::Sha256 => {
+                let mut salted = [0; 32];
+                pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, iterations, &mut salted);
+                salted.to_vec()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum State {
+    Initial,
+    // Client-first message is sent, its bare part is a part of AuthMessage
+    ClientFirst { bare: String },
+    // Client-final message is sent, the server must prove the key
+    ClientFinal { server_signature: Vec<u8> },
+    Done,
+}
+
+/// `SCRAM-SHA-*` mechanism. The password must be already prepared
+/// with SASLprep, ASCII passwords are sent as is
+#[derive(Debug, Clone)]
+pub struct Scram {
+    hash: Hash,
+    mechanism: String,
+    username: String,
+    password: String,
+    nonce: String,
+    // Channel binding type and data, e.g. `tls-exporter` of the TLS session
+    binding: Option<(String, Vec<u8>)>,
+    state: State,
+}
+
+impl Scram {
+    /// `SCRAM-SHA-1`
+    pub fn sha1(username: &str, password: &str) -> Self {
+        Self::new(Hash::Sha1, "SCRAM-SHA-1", username, password)
+    }
+
+    /// `SCRAM-SHA-256`
+    pub fn sha256(username: &str, password: &str) -> Self {
+        Self::new(Hash::Sha256, "SCRAM-SHA-256", username, password)
+    }
+
+    fn new(hash: Hash, mechanism: &str, username: &str, password: &str) -> Self {
+        let mut nonce = [0; NONCE_LENGTH];
+        getrandom::getrandom(&mut nonce).expect("Random source is not available");
+
+        Self {
+            hash,
+            mechanism: mechanism.to_owned(),
+            username: username.to_owned(),
+            password: password.to_owned(),
+            nonce: BASE64.encode(nonce),
+            binding: None,
+            state: State::Initial,
+        }
+    }
+
+    /// Bind the authentication to the TLS session, the mechanism becomes
+    /// `SCRAM-SHA-*-PLUS`. `name` is the channel binding type, e.g.
+    /// `tls-exporter` or `tls-server-end-point`, and `data` is its value,
+    /// see [Client::channel_binding][crate::client::Client::channel_binding]
+    pub fn with_channel_binding(mut self, name: &str, data: Vec<u8>) -> Self {
+        if self.binding.is_none() {
+            self.mechanism.push_str("-PLUS");
+        }
+        self.binding = Some((name.to_owned(), data));
+        self
+    }
+
+    #[cfg(test)]
+    fn with_nonce(mut self, nonce: &str) -> Self {
+        self.nonce = nonce.to_owned();
+        self
+    }
+
+    fn gs2_header(&self) -> String {
+        match &self.binding {
+            Some((name, _)) => format!("p={},,", name),
+            None => "n,,".to_owned(),
+        }
+    }
+
+    fn client_final(&self, bare: &str, server_first: &str) -> Result<(String, Vec<u8>)> {
+        let mut nonce = None;
+        let mut salt = None;
+        let mut iterations = None;
+
+        for attribute in server_first.split(',') {
+            match attribute.split_at(attribute.find('=').unwrap_or(0)) {
+                ("r", v) => nonce = Some(&v[1..]),
+                ("s", v) => salt = BASE64.decode(&v[1..]).ok(),
+                ("i", v) => iterations = v[1..].parse::<u32>().ok(),
+                ("e", v) => return Err(Error::Sasl(v[1..].to_owned())),
+                ("m", _) => return Err(invalid(server_first)),
+                _ => {}
+            }
+        }
+
+        let (nonce, salt, iterations) = match (nonce, salt, iterations) {
+            (Some(nonce), Some(salt), Some(iterations)) if iterations > 0 => {
+                (nonce, salt, iterations)
+            }
+            _ => return Err(invalid(server_first)),
+        };
+        if !nonce.starts_with(&self.nonce) || nonce.len() == self.nonce.len() {
+            return Err(Error::Sasl(
+                "Server nonce does not extend client nonce".to_owned(),
+            ));
+        }
+
+        let mut binding = self.gs2_header().into_bytes();
+        if let Some((_, data)) = &self.binding {
+            binding.extend_from_slice(data);
+        }
+        let without_proof = format!("c={},r={}", BASE64.encode(binding), nonce);
+        let auth_message = format!("{},{},{}", bare, server_first, without_proof);
+
+        let hash = self.hash;
+        let salted_password = hash.hi(self.password.as_bytes(), &salt, iterations);
+        let client_key = hash.hmac(&salted_password, b"Client Key");
+        let stored_key = hash.hash(&client_key);
+        let client_signature = hash.hmac(&stored_key, auth_message.as_bytes());
+        let proof: Vec<u8> = client_key
+            .iter()
+            .zip(client_signature)
+            .map(|(k, s)| k ^ s)
+            .collect();
+
+        let server_key = hash.hmac(&salted_password, b"Server Key");
+        let server_signature = hash.hmac(&server_key, auth_message.as_bytes());
+
+        let client_final = format!("{},p={}", without_proof, BASE64.encode(proof));
+        Ok((client_final, server_signature))
+    }
+}
+
+impl Authenticator for Scram {
+    fn mechanism(&self) -> &str {
+        &self.mechanism
+    }
+
+    fn initial_response(&mut self) -> Option<Vec<u8>> {
+        let bare = format!("n={},r={}", saslname(&self.username), self.nonce);
+        let client_first = format!("{}{}", self.gs2_header(), bare);
+
+        self.state = State::ClientFirst { bare };
+        Some(client_first.into_bytes())
+    }
+
+    fn response(&mut self, challenge: &[u8]) -> Result<Vec<u8>> {
+        let challenge =
+            std::str::from_utf8(challenge).map_err(|_| Error::Parser(challenge.to_vec()))?;
+
+        match std::mem::replace(&mut self.state, State::Done) {
+            State::ClientFirst { bare } => {
+                let (client_final, server_signature) = self.client_final(&bare, challenge)?;
+                self.state = State::ClientFinal { server_signature };
+                Ok(client_final.into_bytes())
+            }
+            State::ClientFinal { server_signature } => {
+                let verifier = match challenge.strip_prefix("v=") {
+                    Some(v) => BASE64.decode(v).map_err(|_| invalid(challenge))?,
+                    None => match challenge.strip_prefix("e=") {
+                        Some(e) => return Err(Error::Sasl(e.to_owned())),
+                        None => return Err(invalid(challenge)),
+                    },
+                };
+                if verifier != server_signature {
+                    return Err(Error::Sasl("Server signature mismatch".to_owned()));
+                }
+                Ok(vec![])
+            }
+            State::Initial | State::Done => Err(Error::Sasl(format!(
+                "Unexpected challenge for {}",
+                self.mechanism
+            ))),
+        }
+    }
+
+    /// The server signature must be verified before the completion,
+    /// otherwise the server is not authenticated
+    fn finish(&mut self) -> Result<()> {
+        match self.state {
+            State::Done => Ok(()),
+            _ => Err(Error::Sasl("Server signature was not received".to_owned())),
+        }
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::Sasl(format!("Invalid server message: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(mut scram: Scram, messages: [&str; 4]) {
+        let [client_first, server_first, client_final, server_final] = messages;
+
+        assert_eq!(scram.initial_response().unwrap(), client_first.as_bytes());
+        assert_eq!(
+            scram.response(server_first.as_bytes()).unwrap(),
+            client_final.as_bytes()
+        );
+        assert!(scram.finish().is_err());
+        assert!(scram.response(server_final.as_bytes()).unwrap().is_empty());
+        assert!(scram.finish().is_ok());
+    }
+
+    #[test]
+    fn rfc5802_sha1() {
+        exchange(
+            Scram::sha1("user", "pencil").with_nonce("fyko+d2lbbFgONRv9qkxdawL"),
+            [
+                "n,,n=user,r=fyko+d2lbbFgONRv9qkxdawL",
+                "r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,s=QSXCR+Q6sek8bf92,i=4096",
+                "c=biws,r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,p=v0X8v3Bz2T0CJGbJQyF0X+HI4Ts=",
+                "v=rmF9pqV8S7suAoZWja4dJRkFsKQ=",
+            ],
+        );
+    }
+
+    #[test]
+    fn rfc7677_sha256() {
+        exchange(
+            Scram::sha256("user", "pencil").with_nonce("rOprNGfwEbeRWgbNEkqO"),
+            [
+                "n,,n=user,r=rOprNGfwEbeRWgbNEkqO",
+                "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096",
+                "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=",
+                "v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=",
+            ],
+        );
+    }
+
+    #[test]
+    fn server_signature_mismatch() {
+        let mut scram = Scram::sha1("user", "pencil").with_nonce("fyko+d2lbbFgONRv9qkxdawL");
+        scram.initial_response();
+        scram
+            .response(b"r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,s=QSXCR+Q6sek8bf92,i=4096")
+            .unwrap();
+
+        assert!(matches!(
+            scram.response(b"v=AAAApqV8S7suAoZWja4dJRkFsKQ="),
+            Err(Error::Sasl(_))
+        ));
+    }
+
+    #[test]
+    fn channel_binding() {
+        let mut scram = Scram::sha256("user", "pencil")
+            .with_channel_binding("tls-exporter", vec![1, 2, 3])
+            .with_nonce("rOprNGfwEbeRWgbNEkqO");
+
+        assert_eq!(scram.mechanism(), "SCRAM-SHA-256-PLUS");
+        assert_eq!(
+            scram.initial_response().unwrap(),
+            b"p=tls-exporter,,n=user,r=rOprNGfwEbeRWgbNEkqO"
+        );
+
+        let client_final = scram
+            .response(b"r=rOprNGfwEbeRWgbNEkqOserver,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096")
+            .unwrap();
+        let expected = format!("c={},", BASE64.encode(b"p=tls-exporter,,\x01\x02\x03"));
+        assert!(client_final.starts_with(expected.as_bytes()));
+    }
+}
@@ -0,0 +1,254 @@
+//! `SEARCH` criteria
+//!
+//! [SearchQuery] is a tree of search keys. Keys listed together must all
+//! match, [SearchQuery::or] and [SearchQuery::not] combine them otherwise:
+//!
+//! ```
+//! use rimap::search::{Date, SearchQuery};
+//! use rimap::types::Month;
+//!
+//! let query = SearchQuery::From("joe@example.com".to_owned())
+//!     .and(SearchQuery::Since(Date::new(1994, Month::Feb, 1)))
+//!     .and(SearchQuery::Seen.not());
+//! ```
+
+use crate::command::Serializer;
+use crate::types::{DateTime, Month, SequenceSet};
+use std::fmt::Display;
+
+/// Date without time used by the date search keys
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Date {
+    /// Day of the month
+    pub day: u8,
+    pub month: Month,
+    pub year: u16,
+}
+
+impl Date {
+    pub fn new(year: u16, month: Month, day: u8) -> Self {
+        Self { day, month, year }
+    }
+}
+
+impl From<&DateTime> for Date {
+    fn from(date: &DateTime) -> Self {
+        Self::new(date.year, date.month, date.day)
+    }
+}
+
+// date-text = date-day "-" date-month "-" date-year
+impl Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}-{:04}", self.day, self.month, self.year)
+    }
+}
+
+/// Result of `SEARCH` with the [SearchQuery::ModSeq] key of CONDSTORE
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SearchResult {
+    /// Sequence numbers or unique identifiers of the matched messages
+    pub numbers: Vec<u32>,
+    /// Highest mod-sequence of the matched messages, `None` if nothing
+    /// matched or the server did not send it
+    pub modseq: Option<u64>,
+}
+
+/// Search key of RFC 3501. Strings are matched as case-insensitive substrings
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SearchQuery {
+    /// All messages in the mailbox
+    All,
+    /// Messages with the `\Answered` flag
+    Answered,
+    /// `BCC` field contains the string
+    Bcc(String),
+    /// Internal date is earlier than the date
+    Before(Date),
+    /// Body of the message contains the string
+    Body(String),
+    /// `CC` field contains the string
+    Cc(String),
+    /// Messages with the `\Deleted` flag
+    Deleted,
+    /// Messages with the `\Draft` flag
+    Draft,
+    /// Messages with the `\Flagged` flag
+    Flagged,
+    /// `FROM` field contains the string
+    From(String),
+    /// Header field contains the string, an empty string matches
+    /// all messages with the field
+    Header(String, String),
+    /// Messages with the keyword flag
+    Keyword(String),
+    /// Size of the message is larger than the number of octets
+    Larger(u32),
+    /// Messages which are recent but not seen
+    New,
+    /// Messages which do not match the key
+    Not(Box<SearchQuery>),
+    /// Messages which are not recent
+    Old,
+    /// Internal date is within the date
+    On(Date),
+    /// Messages which match either key
+    Or(Box<SearchQuery>, Box<SearchQuery>),
+    /// Messages with the `\Recent` flag
+    Recent,
+    /// Messages with the `\Seen` flag
+    Seen,
+    /// `Date` header is earlier than the date
+    SentBefore(Date),
+    /// `Date` header is within the date
+    SentOn(Date),
+    /// `Date` header is within or later than the date
+    SentSince(Date),
+    /// Internal date is within or later than the date
+    Since(Date),
+    /// Size of the message is smaller than the number of octets
+    Smaller(u32),
+    /// `SUBJECT` field contains the string
+    Subject(String),
+    /// Header or body of the message contains the string
+    Text(String),
+    /// `TO` field contains the string
+    To(String),
+    /// Messages with the unique identifiers, e.g. `1:100,200`
+    Uid(SequenceSet),
+    /// Messages without the `\Answered` flag
+    Unanswered,
+    /// Messages without the `\Deleted` flag
+    Undeleted,
+    /// Messages without the `\Draft` flag
+    Undraft,
+    /// Messages without the `\Flagged` flag
+    Unflagged,
+    /// Messages without the keyword flag
+    Unkeyword(String),
+    /// Messages without the `\Seen` flag
+    Unseen,
+    /// Messages with the sequence numbers, e.g. `1:*`
+    SequenceSet(SequenceSet),
+    /// Messages with the mod-sequence equal or greater than the value,
+    /// requires CONDSTORE
+    ModSeq(u64),
+    /// Messages which match all keys, parenthesized when nested.
+    /// Empty list can't be sent
+    And(Vec<SearchQuery>),
+}
+
+impl SearchQuery {
+    /// Messages which match both `self` and `other`
+    pub fn and(self, other: SearchQuery) -> Self {
+        match self {
+            Self::And(mut keys) => {
+                keys.push(other);
+                Self::And(keys)
+            }
+            key => Self::And(vec![key, other]),
+        }
+    }
+
+    /// Messages which match `self` or `other`
+    pub fn or(self, other: SearchQuery) -> Self {
+        Self::Or(Box::new(self), Box::new(other))
+    }
+
+    /// Messages which do not match `self`
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Self::Not(Box::new(self))
+    }
+
+    /// `CHARSET` required for the strings of the query,
+    /// `None` if they are ASCII
+    pub(crate) fn charset(&self) -> Option<&'static str> {
+        let utf8 = |s: &str| if s.is_ascii() { None } else { Some("UTF-8") };
+
+        match self {
+            Self::Bcc(v)
+            | Self::Body(v)
+            | Self::Cc(v)
+            | Self::From(v)
+            | Self::Subject(v)
+            | Self::Text(v)
+            | Self::To(v) => utf8(v),
+            Self::Header(field, value) => utf8(field).or_else(|| utf8(value)),
+            Self::Not(key) => key.charset(),
+            Self::Or(left, right) => left.charset().or_else(|| right.charset()),
+            Self::And(keys) => keys.iter().find_map(SearchQuery::charset),
+            _ => None,
+        }
+    }
+
+    // search-key, the keys of top-level AND are not parenthesized
+    pub(crate) fn serialize<'s>(&self, s: &'s mut Serializer) -> &'s mut Serializer {
+        match self {
+            Self::And(keys) if keys.is_empty() => s.reject("()"),
+            Self::And(keys) => {
+                for (i, key) in keys.iter().enumerate() {
+                    if i > 0 {
+                        s.sp();
+                    }
+                    key.serialize_nested(s);
+                }
+                s
+            }
+            key => key.serialize_nested(s),
+        }
+    }
+
+    fn serialize_nested<'s>(&self, s: &'s mut Serializer) -> &'s mut Serializer {
+        let string =
+            |s: &'s mut Serializer, key, value: &str| s.atom(key).sp().astring(value.as_bytes());
+        let date =
+            |s: &'s mut Serializer, key, value: &Date| s.atom(key).sp().atom(&value.to_string());
+
+        match self {
+            Self::All => s.atom("ALL"),
+            Self::Answered => s.atom("ANSWERED"),
+            Self::Bcc(v) => string(s, "BCC", v),
+            Self::Before(v) => date(s, "BEFORE", v),
+            Self::Body(v) => string(s, "BODY", v),
+            Self::Cc(v) => string(s, "CC", v),
+            Self::Deleted => s.atom("DELETED"),
+            Self::Draft => s.atom("DRAFT"),
+            Self::Flagged => s.atom("FLAGGED"),
+            Self::From(v) => string(s, "FROM", v),
+            Self::Header(field, value) => string(s, "HEADER", field).sp().astring(value.as_bytes()),
+            Self::Keyword(v) => s.atom("KEYWORD").sp().checked_atom(v),
+            Self::Larger(v) => s.atom("LARGER").sp().atom(&v.to_string()),
+            Self::New => s.atom("NEW"),
+            Self::Not(key) => key.serialize_nested(s.atom("NOT").sp()),
+            Self::Old => s.atom("OLD"),
+            Self::On(v) => date(s, "ON", v),
+            Self::Or(left, right) => {
+                left.serialize_nested(s.atom("OR").sp());
+                right.serialize_nested(s.sp())
+            }
+            Self::Recent => s.atom("RECENT"),
+            Self::Seen => s.atom("SEEN"),
+            Self::SentBefore(v) => date(s, "SENTBEFORE", v),
+            Self::SentOn(v) => date(s, "SENTON", v),
+            Self::SentSince(v) => date(s, "SENTSINCE", v),
+            Self::Since(v) => date(s, "SINCE", v),
+            Self::Smaller(v) => s.atom("SMALLER").sp().atom(&v.to_string()),
+            Self::Subject(v) => string(s, "SUBJECT", v),
+            Self::Text(v) => string(s, "TEXT", v),
+            Self::To(v) => string(s, "TO", v),
+            Self::Uid(v) => s.atom("UID").sp().sequence_set(v),
+            Self::Unanswered => s.atom("UNANSWERED"),
+            Self::Undeleted => s.atom("UNDELETED"),
+            Self::Undraft => s.atom("UNDRAFT"),
+            Self::Unflagged => s.atom("UNFLAGGED"),
+            Self::Unkeyword(v) => s.atom("UNKEYWORD").sp().checked_atom(v),
+            Self::Unseen => s.atom("UNSEEN"),
+            Self::SequenceSet(v) => s.sequence_set(v),
+            Self::ModSeq(v) => s.atom("MODSEQ").sp().atom(&v.to_string()),
+            Self::And(keys) if keys.is_empty() => s.reject("()"),
+            Self::And(keys) if keys.len() == 1 => keys[0].serialize_nested(s),
+            Self::And(keys) => s.list(keys, |s, key| key.serialize_nested(s)),
+        }
+    }
+}
@@ -6,7 +6,7 @@ use std::{fmt::Display, iter::Iterator};
 /// `Tag` with prefixed letter
 /// # Example
 /// a1, c1001
-#[derive(Eq, PartialEq, Debug, Hash)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
 pub(crate) struct Tag {
     prefix: char,
     index: u32,
@@ -34,6 +34,7 @@ impl Display for Tag {
 /// Each client command is prefixed with an identifer and a different tag is
 /// generated by the client for each command. Every call `next` method will
 /// return an unique `tag` for the command.
+#[derive(Debug)]
 pub(crate) struct TagGenerator {
     prefix: char,
     index: u32,
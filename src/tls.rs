@@ -0,0 +1,147 @@
+//! TLS support
+//!
+//! Implicit TLS ([Client::connect_tls][crate::client::Client::connect_tls])
+//! and `STARTTLS` ([Client::starttls][crate::client::Client::starttls]) accept
+//! any [TlsConnect] implementation. Implementations for
+//! [rustls](https://docs.rs/tokio-rustls) and
+//! [native-tls](https://docs.rs/tokio-native-tls) connectors are available with
+//! `rustls-tls` and `native-tls` features.
+//!
+//! Channel binding of the session for `SCRAM-SHA-*-PLUS` is available with
+//! [Client::channel_binding][crate::client::Client::channel_binding]: rustls
+//! provides `tls-exporter` of TLS 1.3, native-tls `tls-server-end-point`.
+
+use futures::future::BoxFuture;
+use std::io;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+#[cfg(feature = "native-tls")]
+pub use tokio_native_tls;
+#[cfg(feature = "rustls-tls")]
+pub use tokio_rustls;
+
+/// Channel binding data of the TLS session, RFC 5056
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ChannelBinding {
+    /// Channel binding type, e.g. `tls-exporter`
+    pub name: String,
+    /// Channel binding value
+    pub data: Vec<u8>,
+}
+
+/// TLS handshake on the established stream
+pub trait TlsConnect<S> {
+    /// Encrypted stream
+    type Stream: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+
+    /// Perform the handshake, `domain` is used to verify the server certificate
+    fn connect<'a>(&'a self, domain: &'a str, stream: S)
+        -> BoxFuture<'a, io::Result<Self::Stream>>;
+
+    /// Channel binding of the established session, `None` if the
+    /// implementation can't provide it
+    fn channel_binding(&self, _stream: &Self::Stream) -> Option<ChannelBinding> {
+        None
+    }
+}
+
+#[cfg(feature = "rustls-tls")]
+impl<S> TlsConnect<S> for tokio_rustls::TlsConnector
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    type Stream = tokio_rustls::client::TlsStream<S>;
+
+    fn connect<'a>(
+        &'a self,
+        domain: &'a str,
+        stream: S,
+    ) -> BoxFuture<'a, io::Result<Self::Stream>> {
+        use std::convert::TryFrom;
+        use tokio_rustls::rustls::pki_types::ServerName;
+
+        Box::pin(async move {
+            let domain = ServerName::try_from(domain.to_owned())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+            tokio_rustls::TlsConnector::connect(self, domain, stream).await
+        })
+    }
+
+    // `tls-exporter` of RFC 9266, TLS 1.2 sessions are not bound
+    fn channel_binding(&self, stream: &Self::Stream) -> Option<ChannelBinding> {
+        use tokio_rustls::rustls::ProtocolVersion;
+
+        let (_, connection) = stream.get_ref();
+        if connection.protocol_version() != Some(ProtocolVersion::TLSv1_3) {
+            return None;
+        }
+
+        let data = connection
+            .export_keying_material([0; 32], b"EXPORTER-Channel-Binding", None)
+            .ok()?;
+
+        Some(ChannelBinding {
+            name: "tls-exporter".to_owned(),
+            data: data.to_vec(),
+        })
+    }
+}
+
+#[cfg(feature = "native-tls")]
+impl<S> TlsConnect<S> for tokio_native_tls::TlsConnector
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    type Stream = tokio_native_tls::TlsStream<S>;
+
+    fn connect<'a>(
+        &'a self,
+        domain: &'a str,
+        stream: S,
+    ) -> BoxFuture<'a, io::Result<Self::Stream>> {
+        Box::pin(async move {
+            tokio_native_tls::TlsConnector::connect(self, domain, stream)
+                .await
+                .map_err(io::Error::other)
+        })
+    }
+
+    // `tls-server-end-point` of RFC 5929, the hash of the server certificate
+    fn channel_binding(&self, stream: &Self::Stream) -> Option<ChannelBinding> {
+        let data = stream.get_ref().tls_server_end_point().ok()??;
+
+        Some(ChannelBinding {
+            name: "tls-server-end-point".to_owned(),
+            data,
+        })
+    }
+}
+
+/// Rustls connector which trusts Mozilla root certificates
+#[cfg(feature = "rustls-tls")]
+pub fn rustls_connector() -> tokio_rustls::TlsConnector {
+    use std::sync::Arc;
+    use tokio_rustls::rustls::{crypto::ring, ClientConfig, RootCertStore};
+
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+
+    let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        // Default protocol versions are supported by ring provider
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    tokio_rustls::TlsConnector::from(Arc::new(config))
+}
+
+/// Native-tls connector with system settings
+#[cfg(feature = "native-tls")]
+pub fn native_tls_connector() -> io::Result<tokio_native_tls::TlsConnector> {
+    tokio_native_tls::native_tls::TlsConnector::new()
+        .map(tokio_native_tls::TlsConnector::from)
+        .map_err(io::Error::other)
+}
@@ -0,0 +1,331 @@
+//! Body structure of the message
+
+use super::{owned_opt, Envelope};
+use crate::parser::types as parser;
+use std::borrow::Cow;
+
+/// `(attribute, value)` pairs of the body parameters
+pub type BodyParams = Vec<(String, String)>;
+
+fn params(list: &Option<parser::BodyParams<'_>>) -> Option<BodyParams> {
+    list.as_ref().map(|list| {
+        list.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    })
+}
+
+fn langs(list: &Option<Vec<Cow<'_, str>>>) -> Option<Vec<String>> {
+    list.as_ref()
+        .map(|list| list.iter().map(|v| v.to_string()).collect())
+}
+
+/// MIME structure of the message from `BODY` or `BODYSTRUCTURE`
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Body {
+    /// Non-text single part
+    Basic(BodyTypeBasic),
+    /// `MESSAGE/RFC822` part with the encapsulated message
+    Msg(BodyTypeMsg),
+    /// `TEXT/*` part
+    Text(BodyTypeText),
+    /// `MULTIPART/*` with nested parts
+    Multipart(BodyTypeMpart),
+}
+
+impl From<&parser::Body<'_>> for Body {
+    fn from(body: &parser::Body<'_>) -> Self {
+        match body {
+            parser::Body::Basic(v) => Self::Basic(v.into()),
+            parser::Body::Msg(v) => Self::Msg(v.into()),
+            parser::Body::Text(v) => Self::Text(v.into()),
+            parser::Body::Multipart(v) => Self::Multipart(v.into()),
+        }
+    }
+}
+
+/// Top-level media type of the basic part
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum MediaType {
+    /// `APPLICATION`
+    Application,
+    /// `AUDIO`
+    Audio,
+    /// `IMAGE`
+    Image,
+    /// `MESSAGE` other than `RFC822`
+    Message,
+    /// `VIDEO`
+    Video,
+    /// Any other type
+    Custom(String),
+}
+
+impl From<&parser::MediaType<'_>> for MediaType {
+    fn from(media: &parser::MediaType<'_>) -> Self {
+        match media {
+            parser::MediaType::Application => Self::Application,
+            parser::MediaType::Audio => Self::Audio,
+            parser::MediaType::Image => Self::Image,
+            parser::MediaType::Message => Self::Message,
+            parser::MediaType::Video => Self::Video,
+            parser::MediaType::Custom(v) => Self::Custom(v.to_string()),
+        }
+    }
+}
+
+/// Media type and subtype of the basic part
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct MediaBasic {
+    pub media_type: MediaType,
+    pub subtype: String,
+}
+
+impl From<&parser::MediaBasic<'_>> for MediaBasic {
+    fn from(media: &parser::MediaBasic<'_>) -> Self {
+        Self {
+            media_type: (&media.media_type).into(),
+            subtype: media.subtype.to_string(),
+        }
+    }
+}
+
+/// Content transfer encoding
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum BodyEnc {
+    /// `7BIT`
+    N7bit,
+    /// `8BIT`
+    N8bit,
+    /// `BINARY`
+    Binary,
+    /// `BASE64`
+    Base64,
+    /// `QUOTED-PRINTABLE`
+    QuotedPrintable,
+    /// Any other encoding
+    Custom(String),
+}
+
+impl From<&parser::BodyEnc<'_>> for BodyEnc {
+    fn from(enc: &parser::BodyEnc<'_>) -> Self {
+        match enc {
+            parser::BodyEnc::N7bit => Self::N7bit,
+            parser::BodyEnc::N8bit => Self::N8bit,
+            parser::BodyEnc::Binary => Self::Binary,
+            parser::BodyEnc::Base64 => Self::Base64,
+            parser::BodyEnc::QuotedPrintable => Self::QuotedPrintable,
+            parser::BodyEnc::Custom(v) => Self::Custom(v.to_string()),
+        }
+    }
+}
+
+/// Fields common for all single parts
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BodyFields {
+    /// Parameters of `Content-Type`
+    pub param: Option<BodyParams>,
+    /// `Content-ID`
+    pub id: Option<String>,
+    /// `Content-Description`
+    pub desc: Option<String>,
+    /// `Content-Transfer-Encoding`
+    pub enc: BodyEnc,
+    /// Size of the part in its transfer encoding
+    pub octets: u32,
+}
+
+impl From<&parser::BodyFields<'_>> for BodyFields {
+    fn from(fields: &parser::BodyFields<'_>) -> Self {
+        Self {
+            param: params(&fields.param),
+            id: owned_opt(&fields.id),
+            desc: owned_opt(&fields.desc),
+            enc: (&fields.enc).into(),
+            octets: fields.octets,
+        }
+    }
+}
+
+/// Non-text single part
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BodyTypeBasic {
+    pub media: MediaBasic,
+    pub fields: BodyFields,
+    /// Extension data, only in `BODYSTRUCTURE`
+    pub ext: Option<BodyExt1Part>,
+}
+
+impl From<&parser::BodyTypeBasic<'_>> for BodyTypeBasic {
+    fn from(body: &parser::BodyTypeBasic<'_>) -> Self {
+        Self {
+            media: (&body.media).into(),
+            fields: (&body.fields).into(),
+            ext: body.ext.as_ref().map(BodyExt1Part::from),
+        }
+    }
+}
+
+/// `MESSAGE/RFC822` part
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BodyTypeMsg {
+    pub fields: BodyFields,
+    /// Envelope of the encapsulated message
+    pub envelope: Envelope,
+    /// Structure of the encapsulated message
+    pub body: Box<Body>,
+    /// Size of the part in text lines
+    pub lines: u32,
+    /// Extension data, only in `BODYSTRUCTURE`
+    pub ext: Option<BodyExt1Part>,
+}
+
+impl From<&parser::BodyTypeMsg<'_>> for BodyTypeMsg {
+    fn from(body: &parser::BodyTypeMsg<'_>) -> Self {
+        Self {
+            fields: (&body.fields).into(),
+            envelope: (&body.envelope).into(),
+            body: Box::new(body.body.as_ref().into()),
+            lines: body.lines,
+            ext: body.ext.as_ref().map(BodyExt1Part::from),
+        }
+    }
+}
+
+/// `TEXT/*` part
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BodyTypeText {
+    pub subtype: String,
+    pub fields: BodyFields,
+    /// Size of the part in text lines
+    pub lines: u32,
+    /// Extension data, only in `BODYSTRUCTURE`
+    pub ext: Option<BodyExt1Part>,
+}
+
+impl From<&parser::BodyTypeText<'_>> for BodyTypeText {
+    fn from(body: &parser::BodyTypeText<'_>) -> Self {
+        Self {
+            subtype: body.subtype.to_string(),
+            fields: (&body.fields).into(),
+            lines: body.lines,
+            ext: body.ext.as_ref().map(BodyExt1Part::from),
+        }
+    }
+}
+
+/// `MULTIPART/*` part
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BodyTypeMpart {
+    /// Nested parts in order
+    pub bodies: Vec<Body>,
+    pub subtype: String,
+    /// Extension data, only in `BODYSTRUCTURE`
+    pub ext: Option<BodyExtMpart>,
+}
+
+impl From<&parser::BodyTypeMpart<'_>> for BodyTypeMpart {
+    fn from(body: &parser::BodyTypeMpart<'_>) -> Self {
+        Self {
+            bodies: body.bodies.iter().map(Body::from).collect(),
+            subtype: body.subtype.to_string(),
+            ext: body.ext.as_ref().map(BodyExtMpart::from),
+        }
+    }
+}
+
+/// Extension data of the single part.
+/// Absent fields and NIL are not distinguished
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BodyExt1Part {
+    /// `Content-MD5`
+    pub md5: Option<String>,
+    /// `Content-Disposition`
+    pub dsp: Option<BodyDisposition>,
+    /// `Content-Language`
+    pub lang: Option<Vec<String>>,
+    /// `Content-Location`
+    pub loc: Option<String>,
+    /// Data defined by future extensions
+    pub extension: Vec<BodyExtension>,
+}
+
+impl From<&parser::BodyExt1Part<'_>> for BodyExt1Part {
+    fn from(ext: &parser::BodyExt1Part<'_>) -> Self {
+        Self {
+            md5: owned_opt(&ext.md5),
+            dsp: ext.dsp.as_ref().map(BodyDisposition::from),
+            lang: langs(&ext.lang),
+            loc: owned_opt(&ext.loc),
+            extension: ext.extension.iter().map(BodyExtension::from).collect(),
+        }
+    }
+}
+
+/// Extension data of the multipart.
+/// Absent fields and NIL are not distinguished
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BodyExtMpart {
+    /// Parameters of `Content-Type`
+    pub param: Option<BodyParams>,
+    /// `Content-Disposition`
+    pub dsp: Option<BodyDisposition>,
+    /// `Content-Language`
+    pub lang: Option<Vec<String>>,
+    /// `Content-Location`
+    pub loc: Option<String>,
+    /// Data defined by future extensions
+    pub extension: Vec<BodyExtension>,
+}
+
+impl From<&parser::BodyExtMpart<'_>> for BodyExtMpart {
+    fn from(ext: &parser::BodyExtMpart<'_>) -> Self {
+        Self {
+            param: params(&ext.param),
+            dsp: ext.dsp.as_ref().map(BodyDisposition::from),
+            lang: langs(&ext.lang),
+            loc: owned_opt(&ext.loc),
+            extension: ext.extension.iter().map(BodyExtension::from).collect(),
+        }
+    }
+}
+
+/// `Content-Disposition` of the part
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BodyDisposition {
+    /// Disposition type, e.g. `ATTACHMENT`
+    pub kind: String,
+    pub param: Option<BodyParams>,
+}
+
+impl From<&parser::BodyDisposition<'_>> for BodyDisposition {
+    fn from(dsp: &parser::BodyDisposition<'_>) -> Self {
+        Self {
+            kind: dsp.kind.to_string(),
+            param: params(&dsp.param),
+        }
+    }
+}
+
+/// Extension data defined by future extensions
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BodyExtension {
+    /// String or NIL
+    NString(Option<String>),
+    Number(u32),
+    /// Nested list of extensions
+    List(Vec<BodyExtension>),
+}
+
+impl From<&parser::BodyExtension<'_>> for BodyExtension {
+    fn from(ext: &parser::BodyExtension<'_>) -> Self {
+        match ext {
+            parser::BodyExtension::NString(v) => Self::NString(owned_opt(v)),
+            parser::BodyExtension::Number(v) => Self::Number(*v),
+            parser::BodyExtension::List(v) => {
+                Self::List(v.iter().map(BodyExtension::from).collect())
+            }
+        }
+    }
+}
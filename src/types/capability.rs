@@ -0,0 +1,307 @@
+//! Capabilities advertised by the server
+
+use crate::parser::types as parser;
+use std::{collections::HashSet, fmt::Display, iter::FromIterator};
+
+/// SASL mechanism in `AUTH=<mechanism>` capability
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum AuthMechanism {
+    /// `PLAIN`, RFC 4616
+    Plain,
+    /// `LOGIN`
+    Login,
+    /// `XOAUTH2`
+    XOAuth2,
+    /// `OAUTHBEARER`, RFC 7628
+    OAuthBearer,
+    /// `SCRAM-SHA-1`, RFC 5802
+    ScramSha1,
+    /// `SCRAM-SHA-1-PLUS` with channel binding
+    ScramSha1Plus,
+    /// `SCRAM-SHA-256`, RFC 7677
+    ScramSha256,
+    /// `SCRAM-SHA-256-PLUS` with channel binding
+    ScramSha256Plus,
+    /// `CRAM-MD5`
+    CramMd5,
+    /// `GSSAPI`
+    Gssapi,
+    /// `EXTERNAL`
+    External,
+    /// Unknown mechanism, upper-cased
+    Other(String),
+}
+
+const MECHANISMS: [AuthMechanism; 11] = [
+    AuthMechanism::Plain,
+    AuthMechanism::Login,
+    AuthMechanism::XOAuth2,
+    AuthMechanism::OAuthBearer,
+    AuthMechanism::ScramSha1,
+    AuthMechanism::ScramSha1Plus,
+    AuthMechanism::ScramSha256,
+    AuthMechanism::ScramSha256Plus,
+    AuthMechanism::CramMd5,
+    AuthMechanism::Gssapi,
+    AuthMechanism::External,
+];
+
+impl AuthMechanism {
+    /// Name of the mechanism as sent by the server
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Plain => "PLAIN",
+            Self::Login => "LOGIN",
+            Self::XOAuth2 => "XOAUTH2",
+            Self::OAuthBearer => "OAUTHBEARER",
+            Self::ScramSha1 => "SCRAM-SHA-1",
+            Self::ScramSha1Plus => "SCRAM-SHA-1-PLUS",
+            Self::ScramSha256 => "SCRAM-SHA-256",
+            Self::ScramSha256Plus => "SCRAM-SHA-256-PLUS",
+            Self::CramMd5 => "CRAM-MD5",
+            Self::Gssapi => "GSSAPI",
+            Self::External => "EXTERNAL",
+            Self::Other(v) => v,
+        }
+    }
+}
+
+impl From<&str> for AuthMechanism {
+    fn from(name: &str) -> Self {
+        MECHANISMS
+            .iter()
+            .find(|v| v.as_str().eq_ignore_ascii_case(name))
+            .cloned()
+            .unwrap_or_else(|| Self::Other(name.to_ascii_uppercase()))
+    }
+}
+
+impl Display for AuthMechanism {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Capability advertised by the server
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Capability {
+    /// `IMAP4rev1`, RFC 3501
+    Imap4rev1,
+    /// `IMAP4rev2`, RFC 9051
+    Imap4rev2,
+    /// `STARTTLS`
+    StartTls,
+    /// `LOGINDISABLED`, `LOGIN` command is not allowed
+    LoginDisabled,
+    /// `SASL-IR`, RFC 4959
+    SaslIr,
+    /// `IDLE`, RFC 2177
+    Idle,
+    /// `NAMESPACE`, RFC 2342
+    Namespace,
+    /// `ID`, RFC 2971
+    Id,
+    /// `ENABLE`, RFC 5161
+    Enable,
+    /// `UNSELECT`, RFC 3691
+    Unselect,
+    /// `CHILDREN`, RFC 3348
+    Children,
+    /// `UIDPLUS`, RFC 4315
+    UidPlus,
+    /// `MOVE`, RFC 6851
+    Move,
+    /// `CONDSTORE`, RFC 7162
+    CondStore,
+    /// `QRESYNC`, RFC 7162
+    QResync,
+    /// `LITERAL+`, RFC 7888
+    LiteralPlus,
+    /// `LITERAL-`, RFC 7888
+    LiteralMinus,
+    /// `BINARY`, RFC 3516
+    Binary,
+    /// `ESEARCH`, RFC 4731
+    ESearch,
+    /// `SPECIAL-USE`, RFC 6154
+    SpecialUse,
+    /// `LIST-EXTENDED`, RFC 5258
+    ListExtended,
+    /// `LIST-STATUS`, RFC 5819
+    ListStatus,
+    /// `COMPRESS=DEFLATE`, RFC 4978
+    CompressDeflate,
+    /// `UTF8=ACCEPT`, RFC 6855
+    Utf8Accept,
+    /// `AUTH=<mechanism>`
+    Auth(AuthMechanism),
+    /// Unknown capability, upper-cased
+    Other(String),
+}
+
+const CAPABILITIES: [Capability; 24] = [
+    Capability::Imap4rev1,
+    Capability::Imap4rev2,
+    Capability::StartTls,
+    Capability::LoginDisabled,
+    Capability::SaslIr,
+    Capability::Idle,
+    Capability::Namespace,
+    Capability::Id,
+    Capability::Enable,
+    Capability::Unselect,
+    Capability::Children,
+    Capability::UidPlus,
+    Capability::Move,
+    Capability::CondStore,
+    Capability::QResync,
+    Capability::LiteralPlus,
+    Capability::LiteralMinus,
+    Capability::Binary,
+    Capability::ESearch,
+    Capability::SpecialUse,
+    Capability::ListExtended,
+    Capability::ListStatus,
+    Capability::CompressDeflate,
+    Capability::Utf8Accept,
+];
+
+impl Capability {
+    /// Name of the capability, `AUTH=` capabilities are rendered by [Display]
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Imap4rev1 => "IMAP4rev1",
+            Self::Imap4rev2 => "IMAP4rev2",
+            Self::StartTls => "STARTTLS",
+            Self::LoginDisabled => "LOGINDISABLED",
+            Self::SaslIr => "SASL-IR",
+            Self::Idle => "IDLE",
+            Self::Namespace => "NAMESPACE",
+            Self::Id => "ID",
+            Self::Enable => "ENABLE",
+            Self::Unselect => "UNSELECT",
+            Self::Children => "CHILDREN",
+            Self::UidPlus => "UIDPLUS",
+            Self::Move => "MOVE",
+            Self::CondStore => "CONDSTORE",
+            Self::QResync => "QRESYNC",
+            Self::LiteralPlus => "LITERAL+",
+            Self::LiteralMinus => "LITERAL-",
+            Self::Binary => "BINARY",
+            Self::ESearch => "ESEARCH",
+            Self::SpecialUse => "SPECIAL-USE",
+            Self::ListExtended => "LIST-EXTENDED",
+            Self::ListStatus => "LIST-STATUS",
+            Self::CompressDeflate => "COMPRESS=DEFLATE",
+            Self::Utf8Accept => "UTF8=ACCEPT",
+            Self::Auth(v) => v.as_str(),
+            Self::Other(v) => v,
+        }
+    }
+}
+
+impl From<&str> for Capability {
+    fn from(name: &str) -> Self {
+        CAPABILITIES
+            .iter()
+            .find(|v| v.as_str().eq_ignore_ascii_case(name))
+            .cloned()
+            .unwrap_or_else(|| Self::Other(name.to_ascii_uppercase()))
+    }
+}
+
+impl From<&parser::Capability<'_>> for Capability {
+    fn from(capability: &parser::Capability<'_>) -> Self {
+        match capability {
+            parser::Capability::Auth(v) => Self::Auth((*v).into()),
+            parser::Capability::Other(v) => (*v).into(),
+        }
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auth(v) => write!(f, "AUTH={}", v),
+            v => write!(f, "{}", v.as_str()),
+        }
+    }
+}
+
+/// Set of capabilities advertised by the server
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Capabilities(HashSet<Capability>);
+
+impl Capabilities {
+    /// Check if the server advertises `capability`
+    pub fn contains(&self, capability: &Capability) -> bool {
+        self.0.contains(capability)
+    }
+
+    /// Check if the server advertises `AUTH=<mechanism>`
+    pub fn supports_auth(&self, mechanism: &AuthMechanism) -> bool {
+        self.auth_mechanisms().any(|v| v == mechanism)
+    }
+
+    /// Advertised SASL mechanisms
+    pub fn auth_mechanisms(&self) -> impl Iterator<Item = &AuthMechanism> {
+        self.0.iter().filter_map(|v| match v {
+            Capability::Auth(v) => Some(v),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn insert(&mut self, capability: Capability) {
+        self.0.insert(capability);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Capability> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromIterator<Capability> for Capabilities {
+    fn from_iter<T: IntoIterator<Item = Capability>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Capabilities {
+    type Item = Capability;
+    type IntoIter = std::collections::hash_set::IntoIter<Capability>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_names() {
+        assert_eq!(Capability::from("imap4REV1"), Capability::Imap4rev1);
+        assert_eq!(Capability::from("LITERAL+"), Capability::LiteralPlus);
+        assert_eq!(
+            Capability::from("x-gm-ext-1"),
+            Capability::Other("X-GM-EXT-1".to_owned())
+        );
+        assert_eq!(
+            Capability::from(&parser::Capability::Auth("scram-sha-256")),
+            Capability::Auth(AuthMechanism::ScramSha256)
+        );
+        assert_eq!(
+            Capability::Auth(AuthMechanism::XOAuth2).to_string(),
+            "AUTH=XOAUTH2"
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Mailbox data
+
+use super::{owned_opt, Flag, ListDefinedFlag, StatusInfo};
+use crate::parser::types::{self as parser, ImapResponse, RespCond, RespText, RespTextCode};
+
+/// Attribute of the mailbox name in `LIST` and `LSUB` responses
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ListFlag {
+    /// Attribute defined by RFC 3501
+    Defined(ListDefinedFlag),
+    /// Any other attribute, e.g. `\HasChildren`
+    Extension(String),
+}
+
+impl From<&parser::ListFlag<'_>> for ListFlag {
+    fn from(flag: &parser::ListFlag<'_>) -> Self {
+        match flag {
+            parser::ListFlag::Defined(v) => Self::Defined(*v),
+            parser::ListFlag::Extension(v) => Self::Extension(v.to_string()),
+        }
+    }
+}
+
+/// Mailbox name with attributes from `LIST` or `LSUB`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MailboxInfo {
+    /// Name attributes
+    pub flags: Vec<ListFlag>,
+    /// Hierarchy delimiter, `None` for the flat hierarchy
+    pub delimiter: Option<String>,
+    /// Mailbox name
+    pub name: String,
+}
+
+impl From<&parser::ListMailBox<'_>> for MailboxInfo {
+    fn from(list: &parser::ListMailBox<'_>) -> Self {
+        Self {
+            flags: list.flags.iter().map(ListFlag::from).collect(),
+            delimiter: owned_opt(&list.delimiter),
+            name: list.name.to_string(),
+        }
+    }
+}
+
+/// Mailbox name with requested items from `STATUS`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MailboxStatus {
+    /// Mailbox name
+    pub name: String,
+    /// Status items in order of the response
+    pub status: Vec<StatusInfo>,
+}
+
+impl From<&parser::StatusResponse<'_>> for MailboxStatus {
+    fn from(status: &parser::StatusResponse<'_>) -> Self {
+        Self {
+            name: status.name.to_string(),
+            status: status.status.clone(),
+        }
+    }
+}
+
+/// Mailbox status update
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MailboxData {
+    /// Flags defined in the mailbox
+    Flags(Vec<Flag>),
+    /// `LIST` response
+    List(MailboxInfo),
+    /// `LSUB` response
+    Lsub(MailboxInfo),
+    /// Message numbers matched by `SEARCH` and their highest
+    /// mod-sequence if the search used `MODSEQ`
+    Search(Vec<u32>, Option<u64>),
+    /// `STATUS` response
+    Status(MailboxStatus),
+    /// Number of messages in the mailbox
+    Exists(u32),
+    /// Number of messages with the `\Recent` flag set
+    Recent(u32),
+}
+
+impl From<&parser::MailBoxData<'_>> for MailboxData {
+    fn from(data: &parser::MailBoxData<'_>) -> Self {
+        match data {
+            parser::MailBoxData::Flags(v) => Self::Flags(v.iter().map(Flag::from).collect()),
+            parser::MailBoxData::List(v) => Self::List(v.into()),
+            parser::MailBoxData::Lsub(v) => Self::Lsub(v.into()),
+            parser::MailBoxData::Search(v, modseq) => Self::Search(v.clone(), *modseq),
+            parser::MailBoxData::Status(v) => Self::Status(v.into()),
+            parser::MailBoxData::Exists(v) => Self::Exists(*v),
+            parser::MailBoxData::Recent(v) => Self::Recent(*v),
+        }
+    }
+}
+
+/// State of the mailbox reported by the server on `SELECT` or `EXAMINE`
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Mailbox {
+    /// Flags defined in the mailbox
+    pub flags: Vec<Flag>,
+    /// Number of messages in the mailbox
+    pub exists: u32,
+    /// Number of messages with the `\Recent` flag set
+    pub recent: u32,
+    /// Sequence number of the first unseen message
+    pub unseen: Option<u32>,
+    /// Flags that the client can change permanently.
+    /// Contains [Flag::Perm] if new keywords can be created
+    pub permanent_flags: Vec<Flag>,
+    /// Predicted next unique identifier
+    pub uid_next: Option<u32>,
+    /// Unique identifier validity value
+    pub uid_validity: Option<u32>,
+    /// Mailbox is selected read-only
+    pub read_only: bool,
+    /// Unique identifiers are not persistent, `UIDNOTSTICKY` of UIDPLUS
+    pub uid_not_sticky: bool,
+    /// Highest mod-sequence of CONDSTORE, `None` if the mailbox
+    /// does not support it
+    pub highest_modseq: Option<u64>,
+}
+
+impl Mailbox {
+    /// Update the summary with data from one of the `SELECT` responses.
+    /// Returns `false` if the response does not describe the mailbox
+    pub(crate) fn apply(&mut self, response: &ImapResponse<'_>) -> bool {
+        match response {
+            ImapResponse::Data(parser::UntaggedResponse::MailBox(data)) => match data {
+                parser::MailBoxData::Flags(flags) => {
+                    self.flags = flags.iter().map(Flag::from).collect()
+                }
+                parser::MailBoxData::Exists(v) => self.exists = *v,
+                parser::MailBoxData::Recent(v) => self.recent = *v,
+                _ => return false,
+            },
+            ImapResponse::Data(parser::UntaggedResponse::RespCond(RespCond { text, .. })) => {
+                self.apply_codes(text)
+            }
+            ImapResponse::Response(done) => self.apply_codes(&done.resp.text),
+            _ => return false,
+        }
+        true
+    }
+
+    fn apply_codes(&mut self, text: &RespText<'_>) {
+        for code in &text.code {
+            match code {
+                RespTextCode::PermanentFlags(flags) => {
+                    self.permanent_flags = flags.iter().map(Flag::from).collect()
+                }
+                RespTextCode::UidNext(v) => self.uid_next = Some(*v),
+                RespTextCode::UidValidity(v) => self.uid_validity = Some(*v),
+                RespTextCode::Unseen(v) => self.unseen = Some(*v),
+                RespTextCode::ReadOnly => self.read_only = true,
+                RespTextCode::ReadWrite => self.read_only = false,
+                RespTextCode::UidNotSticky => self.uid_not_sticky = true,
+                RespTextCode::HighestModSeq(v) => self.highest_modseq = Some(*v),
+                RespTextCode::NoModSeq => self.highest_modseq = None,
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Message attributes
+
+use super::{owned_opt, Body, DateTime, DefinedFlag};
+use crate::parser::types as parser;
+use std::{borrow::Cow, fmt::Display};
+
+/// Message flag
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Flag {
+    /// System flag defined by RFC 3501
+    Defined(DefinedFlag),
+    /// Keyword defined by the server or the client, e.g. `$Junk`
+    Keyword(String),
+    /// Unknown system flag starting with `\`
+    Extension(String),
+    /// `\*` in `PERMANENTFLAGS`, new keywords can be created
+    Perm,
+}
+
+impl From<DefinedFlag> for Flag {
+    fn from(flag: DefinedFlag) -> Self {
+        Self::Defined(flag)
+    }
+}
+
+impl From<&parser::Flag<'_>> for Flag {
+    fn from(flag: &parser::Flag<'_>) -> Self {
+        match flag {
+            parser::Flag::Defined(v) => Self::Defined(*v),
+            parser::Flag::Keyword(v) => Self::Keyword(v.to_string()),
+            parser::Flag::Extension(v) => Self::Extension(v.to_string()),
+            parser::Flag::Perm => Self::Perm,
+        }
+    }
+}
+
+impl From<&parser::MsgFlag<'_>> for Flag {
+    fn from(flag: &parser::MsgFlag<'_>) -> Self {
+        match flag {
+            parser::MsgFlag::Common(v) => v.into(),
+            parser::MsgFlag::Recent => Self::Defined(DefinedFlag::Recent),
+        }
+    }
+}
+
+impl Display for Flag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Defined(v) => write!(f, "{}", v),
+            Self::Keyword(v) | Self::Extension(v) => write!(f, "{}", v),
+            Self::Perm => write!(f, "\\*"),
+        }
+    }
+}
+
+/// Address from the envelope. Start and end of a group are marked with
+/// addresses without `host`
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Address {
+    /// Personal name
+    pub name: Option<String>,
+    /// Source route
+    pub adl: Option<String>,
+    /// Mailbox name, the part before `@`
+    pub mailbox: Option<String>,
+    /// Host name, the part after `@`
+    pub host: Option<String>,
+}
+
+impl From<&parser::Address<'_>> for Address {
+    fn from(address: &parser::Address<'_>) -> Self {
+        Self {
+            name: owned_opt(&address.name),
+            adl: owned_opt(&address.adl),
+            mailbox: owned_opt(&address.mailbox),
+            host: owned_opt(&address.host),
+        }
+    }
+}
+
+/// Envelope structure of the message
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Envelope {
+    /// `Date` header as is
+    pub date: Option<String>,
+    pub subject: Option<String>,
+    pub from: Option<Vec<Address>>,
+    pub sender: Option<Vec<Address>>,
+    pub reply_to: Option<Vec<Address>>,
+    pub to: Option<Vec<Address>>,
+    pub cc: Option<Vec<Address>>,
+    pub bcc: Option<Vec<Address>>,
+    pub in_reply_to: Option<String>,
+    pub message_id: Option<String>,
+}
+
+fn addresses(list: &Option<Vec<parser::Address<'_>>>) -> Option<Vec<Address>> {
+    list.as_ref()
+        .map(|list| list.iter().map(Address::from).collect())
+}
+
+impl From<&parser::Envelope<'_>> for Envelope {
+    fn from(envelope: &parser::Envelope<'_>) -> Self {
+        Self {
+            date: owned_opt(&envelope.date),
+            subject: owned_opt(&envelope.subject),
+            from: addresses(&envelope.from),
+            sender: addresses(&envelope.sender),
+            reply_to: addresses(&envelope.reply_to),
+            to: addresses(&envelope.to),
+            cc: addresses(&envelope.cc),
+            bcc: addresses(&envelope.bcc),
+            in_reply_to: owned_opt(&envelope.in_reply_to),
+            message_id: owned_opt(&envelope.message_id),
+        }
+    }
+}
+
+/// Part of the message in `BODY[<section>]`
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Section {
+    /// Part numbers, empty for the whole message
+    pub part: Vec<u32>,
+    pub text: Option<SectionText>,
+}
+
+/// Text part specifier of the section
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum SectionText {
+    /// `HEADER`
+    Header,
+    /// `HEADER.FIELDS (...)`
+    HeaderFields(Vec<String>),
+    /// `HEADER.FIELDS.NOT (...)`
+    HeaderFieldsNot(Vec<String>),
+    /// `TEXT`
+    Text,
+    /// `MIME`
+    Mime,
+}
+
+impl From<&parser::Section<'_>> for Section {
+    fn from(section: &parser::Section<'_>) -> Self {
+        let fields = |list: &[Cow<'_, str>]| list.iter().map(|v| v.to_string()).collect();
+
+        Self {
+            part: section.part.clone(),
+            text: section.text.as_ref().map(|text| match text {
+                parser::SectionText::Header => SectionText::Header,
+                parser::SectionText::HeaderFields(v) => SectionText::HeaderFields(fields(v)),
+                parser::SectionText::HeaderFieldsNot(v) => SectionText::HeaderFieldsNot(fields(v)),
+                parser::SectionText::Text => SectionText::Text,
+                parser::SectionText::Mime => SectionText::Mime,
+            }),
+        }
+    }
+}
+
+/// Data item of the `FETCH` response
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MsgAtt {
+    /// `ENVELOPE`
+    Envelope(Envelope),
+    /// `INTERNALDATE`
+    InternalDate(DateTime),
+    /// `RFC822`
+    Rfc822(Option<Vec<u8>>),
+    /// `RFC822.HEADER`
+    Rfc822Header(Option<Vec<u8>>),
+    /// `RFC822.TEXT`
+    Rfc822Text(Option<Vec<u8>>),
+    /// `RFC822.SIZE`
+    Rfc822Size(u32),
+    /// `BODY`, structure without extension data
+    Body(Body),
+    /// `BODYSTRUCTURE`
+    BodyStructure(Body),
+    /// `BODY[<section>]<<origin>>`
+    BodySection {
+        /// Requested section
+        section: Section,
+        /// First octet of the partial data
+        origin: Option<u32>,
+        /// Content of the section
+        data: Option<Vec<u8>>,
+    },
+    /// `UID`
+    Uid(u32),
+    /// `FLAGS`
+    Flags(Vec<Flag>),
+    /// `MODSEQ`, mod-sequence of the message
+    ModSeq(u64),
+}
+
+impl From<&parser::MsgAtt<'_>> for MsgAtt {
+    fn from(att: &parser::MsgAtt<'_>) -> Self {
+        let bytes = |data: &Option<&[u8]>| data.map(<[u8]>::to_vec);
+
+        match att {
+            parser::MsgAtt::Envelope(v) => Self::Envelope(v.into()),
+            parser::MsgAtt::InternalDate(v) => Self::InternalDate(*v),
+            parser::MsgAtt::Rfc822(v) => Self::Rfc822(bytes(v)),
+            parser::MsgAtt::Rfc822Header(v) => Self::Rfc822Header(bytes(v)),
+            parser::MsgAtt::Rfc822Text(v) => Self::Rfc822Text(bytes(v)),
+            parser::MsgAtt::Rfc822Size(v) => Self::Rfc822Size(*v),
+            parser::MsgAtt::Body(v) => Self::Body(v.into()),
+            parser::MsgAtt::BodyStructure(v) => Self::BodyStructure(v.into()),
+            parser::MsgAtt::BodySection {
+                section,
+                origin,
+                data,
+            } => Self::BodySection {
+                section: section.into(),
+                origin: *origin,
+                data: bytes(data),
+            },
+            parser::MsgAtt::Uid(v) => Self::Uid(*v),
+            parser::MsgAtt::Flags(v) => Self::Flags(v.iter().map(Flag::from).collect()),
+            parser::MsgAtt::ModSeq(v) => Self::ModSeq(*v),
+        }
+    }
+}
+
+/// Message status update
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MessageData {
+    /// Message with the sequence number was removed
+    Expunge(u32),
+    /// Sequence number and attributes of the message
+    Fetch(u32, Vec<MsgAtt>),
+}
+
+impl From<&parser::MessageData<'_>> for MessageData {
+    fn from(data: &parser::MessageData<'_>) -> Self {
+        match data {
+            parser::MessageData::Expunge(v) => Self::Expunge(*v),
+            parser::MessageData::Fetch(n, v) => {
+                Self::Fetch(*n, v.iter().map(MsgAtt::from).collect())
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Owned response model
+//!
+//! The parser borrows everything from the network buffer. Values returned by
+//! the [Client][crate::client::Client] are converted into the types below, so
+//! they can be stored and sent across tasks. Quoted strings are unescaped by
+//! the parser, literals are taken as is.
+
+mod body;
+mod capability;
+mod mailbox;
+mod message;
+mod response;
+mod sequence;
+
+pub use crate::parser::types::{DateTime, DefinedFlag, ListDefinedFlag, Month, StatusInfo, Time};
+pub use body::{
+    Body, BodyDisposition, BodyEnc, BodyExt1Part, BodyExtMpart, BodyExtension, BodyFields,
+    BodyParams, BodyTypeBasic, BodyTypeMpart, BodyTypeMsg, BodyTypeText, MediaBasic, MediaType,
+};
+pub use capability::{AuthMechanism, Capabilities, Capability};
+pub use mailbox::{ListFlag, Mailbox, MailboxData, MailboxInfo, MailboxStatus};
+pub use message::{Address, Envelope, Flag, MessageData, MsgAtt, Section, SectionText};
+pub use response::{
+    AppendUid, CopyUid, Expunged, Moved, RespCond, RespText, RespTextCode, Status, UntaggedResponse,
+};
+pub use sequence::{SeqNumber, SequenceSet};
+use std::borrow::Cow;
+
+// Strings are unescaped by the parser already
+fn owned_opt(s: &Option<Cow<'_, str>>) -> Option<String> {
+    s.as_deref().map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DefinedFlag, Envelope, Flag, MailboxData, MessageData, MsgAtt};
+    use crate::parser::{
+        parse_response,
+        types::{ImapResponse, UntaggedResponse},
+    };
+
+    #[test]
+    fn convert_fetch() {
+        let response = b"* 1 FETCH (FLAGS (\\SEEN $Junk) ENVELOPE (NIL \"a \\\"b\\\"\" \
+                         NIL NIL NIL NIL NIL NIL NIL NIL))\r\n";
+
+        let data = match parse_response(response).unwrap() {
+            (_, ImapResponse::Data(UntaggedResponse::Message(data))) => MessageData::from(&data),
+            (_, v) => panic!("Unexpected response {:?}", v),
+        };
+
+        let envelope = Envelope {
+            subject: Some("a \"b\"".to_owned()),
+            ..Envelope::default()
+        };
+
+        assert_eq!(
+            data,
+            MessageData::Fetch(
+                1,
+                vec![
+                    MsgAtt::Flags(vec![
+                        Flag::Defined(DefinedFlag::Seen),
+                        Flag::Keyword("$Junk".to_owned())
+                    ]),
+                    MsgAtt::Envelope(envelope)
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn convert_literal() {
+        let response = b"* 1 FETCH (ENVELOPE (NIL {6}\r\nC:\\tmp \
+                         NIL NIL NIL NIL NIL NIL NIL NIL))\r\n";
+
+        let data = match parse_response(response).unwrap() {
+            (_, ImapResponse::Data(UntaggedResponse::Message(data))) => MessageData::from(&data),
+            (_, v) => panic!("Unexpected response {:?}", v),
+        };
+        let envelope = Envelope {
+            subject: Some("C:\\tmp".to_owned()),
+            ..Envelope::default()
+        };
+        assert_eq!(
+            data,
+            MessageData::Fetch(1, vec![MsgAtt::Envelope(envelope)])
+        );
+
+        let response = b"* LIST () \"\\\\\" {5}\r\na\\b\\c\r\n";
+
+        let list = match parse_response(response).unwrap() {
+            (_, ImapResponse::Data(UntaggedResponse::MailBox(data))) => MailboxData::from(&data),
+            (_, v) => panic!("Unexpected response {:?}", v),
+        };
+        match list {
+            MailboxData::List(info) => {
+                assert_eq!(info.delimiter.as_deref(), Some("\\"));
+                assert_eq!(info.name, "a\\b\\c");
+            }
+            v => panic!("Unexpected data {:?}", v),
+        }
+    }
+}
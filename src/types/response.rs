@@ -0,0 +1,258 @@
+//! Status responses and untagged data
+
+use super::{owned_opt, Capabilities, Capability, Flag, MailboxData, MessageData, SequenceSet};
+use crate::parser::types as parser;
+use std::collections::HashMap;
+
+/// Result of the command
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Status {
+    /// `OK`
+    Ok,
+    /// `NO`, operational error
+    No,
+    /// `BAD`, protocol error
+    Bad,
+}
+
+impl From<&parser::ImapResult> for Status {
+    fn from(status: &parser::ImapResult) -> Self {
+        match status {
+            parser::ImapResult::Ok => Self::Ok,
+            parser::ImapResult::No => Self::No,
+            parser::ImapResult::Bad => Self::Bad,
+        }
+    }
+}
+
+/// Unique identifiers assigned to the appended messages, `APPENDUID`
+/// code of UIDPLUS
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AppendUid {
+    /// `UIDVALIDITY` of the destination mailbox
+    pub uid_validity: u32,
+    pub uids: SequenceSet,
+}
+
+/// Unique identifiers of the copied messages, `COPYUID` code of UIDPLUS
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CopyUid {
+    /// `UIDVALIDITY` of the destination mailbox
+    pub uid_validity: u32,
+    /// Identifiers of the source messages in order of the response
+    pub source: Vec<u32>,
+    /// Identifiers of the copies in the same order as `source`,
+    /// e.g. `destination[0]` is the copy of `source[0]`
+    pub destination: Vec<u32>,
+}
+
+/// Messages moved by `MOVE`
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Moved {
+    /// Identifiers of the moved messages if the server supports UIDPLUS
+    pub copy_uid: Option<CopyUid>,
+    /// Sequence numbers of `EXPUNGE` responses in order of arrival,
+    /// each of them is relative to the previous ones
+    pub expunged: Vec<u32>,
+    /// Unique identifiers of `VANISHED` responses, which replace
+    /// `EXPUNGE` once QRESYNC is enabled
+    pub vanished: SequenceSet,
+}
+
+/// Messages removed by `EXPUNGE` or `UID EXPUNGE`
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Expunged {
+    /// Sequence numbers of `EXPUNGE` responses in order of arrival,
+    /// each of them is relative to the previous ones
+    pub messages: Vec<u32>,
+    /// Unique identifiers of `VANISHED` responses, which replace
+    /// `EXPUNGE` once QRESYNC is enabled
+    pub vanished: SequenceSet,
+}
+
+/// Response code in square brackets before the human-readable text
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RespTextCode {
+    /// `ALERT`, the text must be presented to the user
+    Alert,
+    /// `BADCHARSET` with supported charsets
+    BadCharset(Vec<String>),
+    /// `CAPABILITY` list
+    Capability(Capabilities),
+    /// `PARSE`, the server failed to parse a message
+    Parse,
+    /// `PERMANENTFLAGS`
+    PermanentFlags(Vec<Flag>),
+    /// `READ-ONLY`
+    ReadOnly,
+    /// `READ-WRITE`
+    ReadWrite,
+    /// `TRYCREATE`, the target mailbox does not exist
+    TryCreate,
+    /// `UIDNEXT`
+    UidNext(u32),
+    /// `UIDVALIDITY`
+    UidValidity(u32),
+    /// `UNSEEN`
+    Unseen(u32),
+    /// `APPENDUID`
+    AppendUid(AppendUid),
+    /// `COPYUID`
+    CopyUid(CopyUid),
+    /// `UIDNOTSTICKY`, the mailbox does not support persistent identifiers
+    UidNotSticky,
+    /// `HIGHESTMODSEQ` of the mailbox
+    HighestModSeq(u64),
+    /// `NOMODSEQ`, the mailbox does not support mod-sequences
+    NoModSeq,
+    /// `MODIFIED`, messages which failed the `UNCHANGEDSINCE` test of `STORE`
+    Modified(SequenceSet),
+    /// Unknown code with optional text
+    Other(String, Option<String>),
+}
+
+impl From<&parser::RespTextCode<'_>> for RespTextCode {
+    fn from(code: &parser::RespTextCode<'_>) -> Self {
+        match code {
+            parser::RespTextCode::Alert => Self::Alert,
+            parser::RespTextCode::BadCharset(v) => {
+                Self::BadCharset(v.iter().map(|v| v.to_string()).collect())
+            }
+            parser::RespTextCode::Capability(v) => {
+                Self::Capability(v.iter().map(Capability::from).collect())
+            }
+            parser::RespTextCode::Parse => Self::Parse,
+            parser::RespTextCode::PermanentFlags(v) => {
+                Self::PermanentFlags(v.iter().map(Flag::from).collect())
+            }
+            parser::RespTextCode::ReadOnly => Self::ReadOnly,
+            parser::RespTextCode::ReadWrite => Self::ReadWrite,
+            parser::RespTextCode::TryCreate => Self::TryCreate,
+            parser::RespTextCode::UidNext(v) => Self::UidNext(*v),
+            parser::RespTextCode::UidValidity(v) => Self::UidValidity(*v),
+            parser::RespTextCode::Unseen(v) => Self::Unseen(*v),
+            parser::RespTextCode::AppendUid(uid_validity, uids) => Self::AppendUid(AppendUid {
+                uid_validity: *uid_validity,
+                uids: uids.clone(),
+            }),
+            parser::RespTextCode::CopyUid(uid_validity, source, destination) => {
+                Self::CopyUid(CopyUid {
+                    uid_validity: *uid_validity,
+                    source: source.clone(),
+                    destination: destination.clone(),
+                })
+            }
+            parser::RespTextCode::UidNotSticky => Self::UidNotSticky,
+            parser::RespTextCode::HighestModSeq(v) => Self::HighestModSeq(*v),
+            parser::RespTextCode::NoModSeq => Self::NoModSeq,
+            parser::RespTextCode::Modified(v) => Self::Modified(v.clone()),
+            parser::RespTextCode::Other(name, text) => {
+                Self::Other(name.to_string(), text.map(str::to_owned))
+            }
+        }
+    }
+}
+
+/// Text of the status response
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct RespText {
+    /// Response codes
+    pub code: Vec<RespTextCode>,
+    /// Human-readable text
+    pub text: String,
+}
+
+impl RespText {
+    /// `APPENDUID` code of the completion of `APPEND`
+    pub fn append_uid(&self) -> Option<&AppendUid> {
+        self.code.iter().find_map(|code| match code {
+            RespTextCode::AppendUid(v) => Some(v),
+            _ => None,
+        })
+    }
+
+    /// `COPYUID` code of the completion of `COPY`
+    pub fn copy_uid(&self) -> Option<&CopyUid> {
+        self.code.iter().find_map(|code| match code {
+            RespTextCode::CopyUid(v) => Some(v),
+            _ => None,
+        })
+    }
+}
+
+impl From<&parser::RespText<'_>> for RespText {
+    fn from(text: &parser::RespText<'_>) -> Self {
+        Self {
+            code: text.code.iter().map(RespTextCode::from).collect(),
+            text: text.text.to_owned(),
+        }
+    }
+}
+
+/// Status response
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RespCond {
+    pub status: Status,
+    pub text: RespText,
+}
+
+impl From<&parser::RespCond<'_>> for RespCond {
+    fn from(cond: &parser::RespCond<'_>) -> Self {
+        Self {
+            status: (&cond.status).into(),
+            text: (&cond.text).into(),
+        }
+    }
+}
+
+/// Untagged response sent by the server
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum UntaggedResponse {
+    /// `* OK`, `* NO` or `* BAD`
+    Cond(RespCond),
+    /// `* BYE`, the server is about to close the connection
+    Bye(RespText),
+    /// Mailbox status update
+    Mailbox(MailboxData),
+    /// Message status update
+    Message(MessageData),
+    /// `* CAPABILITY`
+    Capability(Capabilities),
+    /// `* VANISHED` of QRESYNC, unique identifiers of expunged messages
+    Vanished {
+        /// `(EARLIER)`, the messages were expunged before the command
+        earlier: bool,
+        uids: SequenceSet,
+    },
+    /// `* ENABLED`, extensions enabled by `ENABLE`
+    Enabled(Capabilities),
+    /// `* ID`, fields and values describing the server. Empty if the
+    /// server sends `NIL`
+    Id(HashMap<String, Option<String>>),
+}
+
+impl From<&parser::UntaggedResponse<'_>> for UntaggedResponse {
+    fn from(response: &parser::UntaggedResponse<'_>) -> Self {
+        match response {
+            parser::UntaggedResponse::RespCond(v) => Self::Cond(v.into()),
+            parser::UntaggedResponse::RespBye(v) => Self::Bye((&v.resp).into()),
+            parser::UntaggedResponse::MailBox(v) => Self::Mailbox(v.into()),
+            parser::UntaggedResponse::Message(v) => Self::Message(v.into()),
+            parser::UntaggedResponse::Capability(v) => {
+                Self::Capability(v.iter().map(Capability::from).collect())
+            }
+            parser::UntaggedResponse::Vanished(earlier, uids) => Self::Vanished {
+                earlier: *earlier,
+                uids: uids.clone(),
+            },
+            parser::UntaggedResponse::Enabled(v) => {
+                Self::Enabled(v.iter().map(Capability::from).collect())
+            }
+            parser::UntaggedResponse::Id(v) => Self::Id(
+                v.iter()
+                    .map(|(field, value)| (field.to_string(), owned_opt(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
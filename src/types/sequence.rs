@@ -0,0 +1,252 @@
+//! Sets of message sequence numbers and unique identifiers
+
+use crate::error::Error;
+use std::{fmt::Display, iter::FromIterator, str::FromStr};
+
+/// Message sequence number or unique identifier in [SequenceSet]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum SeqNumber {
+    /// Number greater than zero
+    Value(u32),
+    /// `*`, the largest number in use in the mailbox
+    Largest,
+}
+
+impl SeqNumber {
+    // `*` is ordered after every number
+    fn key(self) -> u64 {
+        match self {
+            Self::Value(v) => v as u64,
+            Self::Largest => u32::MAX as u64 + 1,
+        }
+    }
+
+    fn from_key(key: u64) -> Self {
+        match key > u32::MAX as u64 {
+            true => Self::Largest,
+            false => Self::Value(key as u32),
+        }
+    }
+}
+
+impl From<u32> for SeqNumber {
+    fn from(value: u32) -> Self {
+        Self::Value(value)
+    }
+}
+
+impl Display for SeqNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Value(v) => write!(f, "{}", v),
+            Self::Largest => write!(f, "*"),
+        }
+    }
+}
+
+/// `sequence-set` of message sequence numbers or unique identifiers.
+/// Ranges are kept sorted and merged, so the set is sent compactly,
+/// e.g. `1,2,3,5` as `1:3,5`
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct SequenceSet {
+    // Sorted disjoint inclusive ranges of SeqNumber::key,
+    // adjacent ranges are merged
+    ranges: Vec<(u64, u64)>,
+}
+
+impl SequenceSet {
+    /// Empty set, it can't be sent to the server
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `1:*`, all messages of the mailbox
+    pub fn all() -> Self {
+        Self::range(1, SeqNumber::Largest)
+    }
+
+    /// Set of one number. Numbers start from 1, a set with zero
+    /// can't be sent to the server
+    pub fn single(number: u32) -> Self {
+        Self::range(number, number)
+    }
+
+    /// Inclusive range, the order of the ends does not matter
+    pub fn range<A, B>(start: A, end: B) -> Self
+    where
+        A: Into<SeqNumber>,
+        B: Into<SeqNumber>,
+    {
+        let mut set = Self::new();
+        set.insert_range(start, end);
+        set
+    }
+
+    /// Add `number` to the set
+    pub fn insert(&mut self, number: u32) {
+        self.insert_range(number, number)
+    }
+
+    /// Add the inclusive range to the set
+    pub fn insert_range<A, B>(&mut self, start: A, end: B)
+    where
+        A: Into<SeqNumber>,
+        B: Into<SeqNumber>,
+    {
+        let (a, b) = (start.into().key(), end.into().key());
+        let (mut start, mut end) = (a.min(b), a.max(b));
+
+        // Ranges which overlap or touch the new one are merged into it
+        let first = self.ranges.partition_point(|&(_, e)| e + 1 < start);
+        let last = self.ranges.partition_point(|&(s, _)| s <= end + 1);
+        if first < last {
+            start = start.min(self.ranges[first].0);
+            end = end.max(self.ranges[last - 1].1);
+        }
+        self.ranges
+            .splice(first..last, std::iter::once((start, end)));
+    }
+
+    /// Union of both sets
+    pub fn union(mut self, other: &SequenceSet) -> Self {
+        for &(start, end) in &other.ranges {
+            self.insert_range(SeqNumber::from_key(start), SeqNumber::from_key(end));
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Check if `number` is in the set, `*` is taken as unbounded
+    pub fn contains(&self, number: u32) -> bool {
+        let key = number as u64;
+        self.ranges.iter().any(|&(s, e)| s <= key && key <= e)
+    }
+
+    /// Replace `*` with the largest number in use, e.g. from `EXISTS`
+    /// or `UIDNEXT` - 1
+    pub fn resolve(&self, largest: u32) -> Self {
+        let mut set = Self::new();
+        for &(start, end) in &self.ranges {
+            let resolve = |key| match SeqNumber::from_key(key) {
+                SeqNumber::Largest => largest,
+                SeqNumber::Value(v) => v,
+            };
+            set.insert_range(resolve(start), resolve(end));
+        }
+        set
+    }
+
+    /// Ranges of the set in ascending order, single numbers have equal ends
+    pub fn ranges(&self) -> impl Iterator<Item = (SeqNumber, SeqNumber)> + '_ {
+        self.ranges
+            .iter()
+            .map(|&(s, e)| (SeqNumber::from_key(s), SeqNumber::from_key(e)))
+    }
+
+    /// Numbers of the set in ascending order. `*` is unbounded here,
+    /// so [SequenceSet::resolve] it first
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        let clamp = |key: u64| key.min(u32::MAX as u64) as u32;
+        self.ranges
+            .iter()
+            .flat_map(move |&(s, e)| clamp(s)..=clamp(e))
+    }
+}
+
+impl From<u32> for SequenceSet {
+    fn from(number: u32) -> Self {
+        Self::single(number)
+    }
+}
+
+impl FromIterator<u32> for SequenceSet {
+    fn from_iter<T: IntoIterator<Item = u32>>(iter: T) -> Self {
+        let mut set = Self::new();
+        for number in iter {
+            set.insert(number);
+        }
+        set
+    }
+}
+
+// sequence-set = (seq-number | seq-range) *("," sequence-set)
+impl Display for SequenceSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (start, end)) in self.ranges().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            match start == end {
+                true => write!(f, "{}", start)?,
+                false => write!(f, "{}:{}", start, end)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SequenceSet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |v: &str| match v {
+            "*" => Ok(SeqNumber::Largest),
+            v if v.starts_with('+') => Err(Error::Parser(s.as_bytes().to_vec())),
+            v => match v.parse::<u32>() {
+                Ok(v) if v > 0 => Ok(SeqNumber::Value(v)),
+                _ => Err(Error::Parser(s.as_bytes().to_vec())),
+            },
+        };
+
+        let mut set = Self::new();
+        for range in s.split(',') {
+            match range.split_once(':') {
+                Some((start, end)) => set.insert_range(number(start)?, number(end)?),
+                None => {
+                    let number = number(range)?;
+                    set.insert_range(number, number)
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact() {
+        let set: SequenceSet = vec![5, 1, 3, 2, 9, 10].into_iter().collect();
+        assert_eq!(set.to_string(), "1:3,5,9:10");
+
+        let set = set.union(&SequenceSet::range(SeqNumber::Largest, 4));
+        assert_eq!(set.to_string(), "1:*");
+
+        let mut set = SequenceSet::range(20, 10);
+        set.insert_range(1, 5);
+        set.insert(7);
+        set.insert_range(6, 8);
+        assert_eq!(set.to_string(), "1:8,10:20");
+    }
+
+    #[test]
+    fn parse() {
+        let set: SequenceSet = "4,1:2,7:*,3".parse().unwrap();
+
+        assert_eq!(set.to_string(), "1:4,7:*");
+        assert!(set.contains(100) && !set.contains(5));
+        assert_eq!(
+            set.resolve(9).iter().collect::<Vec<_>>(),
+            [1, 2, 3, 4, 7, 8, 9]
+        );
+
+        for invalid in ["", "0", "1,", "1:x", "+1"] {
+            assert!(invalid.parse::<SequenceSet>().is_err());
+        }
+    }
+}
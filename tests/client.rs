@@ -0,0 +1,1236 @@
+mod common;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use common::{listen, play, serve, Step, Step::*};
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use rimap::{
+    client::{Client, StatusAttribute, StoreMode},
+    error::Error,
+    fetch::{FetchItem, FetchItems},
+    sasl,
+    search::{Date, SearchQuery},
+    types::{
+        AppendUid, AuthMechanism, Capability, CopyUid, DateTime, DefinedFlag, Flag,
+        ListDefinedFlag, ListFlag, MailboxData, MailboxInfo, MailboxStatus, MessageData, Month,
+        Moved, RespTextCode, Section, SectionText, SeqNumber, SequenceSet, StatusInfo, Time,
+        UntaggedResponse,
+    },
+};
+use sha2::Sha256;
+use std::time::Duration;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+const GREETING: Step = S("* OK IMAP4rev1 Service Ready");
+
+#[tokio::test]
+async fn login_and_logout() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 LOGIN user \"pa\\\"ss\""),
+        S("a0 OK LOGIN completed"),
+        C("a1 LOGOUT"),
+        S("* BYE IMAP4rev1 Server logging out"),
+        S("a1 OK LOGOUT completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.login("user", "pa\"ss").await.unwrap();
+    client.logout().await.unwrap();
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn login_rejected() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 LOGIN user wrong"),
+        S("a0 NO LOGIN failed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let result = client.login("user", "wrong").await;
+
+    assert!(matches!(result, Err(Error::No(text)) if text == "LOGIN failed"));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn login_literal() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 LOGIN user {9}"),
+        S("* OK [ALERT] Password will expire soon"),
+        S("+ Ready for literal data"),
+        C("pässword"),
+        S("a0 OK LOGIN completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.login("user", "pässword").await.unwrap();
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn literal_rejected() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 LOGIN user {9}"),
+        S("a0 BAD Literal too long"),
+        C("a1 CAPABILITY"),
+        S("a1 OK CAPABILITY completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let result = client.login("user", "pässword").await;
+
+    assert!(matches!(result, Err(Error::Bad(text)) if text == "Literal too long"));
+    // The literal is not sent, the connection is usable
+    client.capability().await.unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn greeting_bye() {
+    let (addr, server) = serve(vec![S("* BYE Too many connections")]).await;
+
+    let result = Client::connect(addr).await;
+
+    assert!(matches!(result, Err(Error::Bye(text)) if text == "Too many connections"));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn select() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 SELECT INBOX"),
+        S("* 172 EXISTS"),
+        S("* 1 RECENT"),
+        S("* OK [UNSEEN 12] Message 12 is first unseen"),
+        S("* OK [UIDVALIDITY 3857529045] UIDs valid"),
+        S("* OK [UIDNEXT 4392] Predicted next UID"),
+        S("* FLAGS (\\Answered \\Flagged \\Deleted \\Seen \\Draft)"),
+        S("* OK [PERMANENTFLAGS (\\Deleted \\Seen \\*)] Limited"),
+        S("a0 OK [READ-WRITE] SELECT completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let mailbox = client.select("INBOX").await.unwrap();
+
+    assert_eq!(mailbox.exists, 172);
+    assert_eq!(mailbox.recent, 1);
+    assert_eq!(mailbox.unseen, Some(12));
+    assert_eq!(mailbox.uid_validity, Some(3857529045));
+    assert_eq!(mailbox.uid_next, Some(4392));
+    assert_eq!(
+        mailbox.flags,
+        vec![
+            Flag::Defined(DefinedFlag::Answered),
+            Flag::Defined(DefinedFlag::Flagged),
+            Flag::Defined(DefinedFlag::Deleted),
+            Flag::Defined(DefinedFlag::Seen),
+            Flag::Defined(DefinedFlag::Draft),
+        ]
+    );
+    assert_eq!(
+        mailbox.permanent_flags,
+        vec![
+            Flag::Defined(DefinedFlag::Deleted),
+            Flag::Defined(DefinedFlag::Seen),
+            Flag::Perm,
+        ]
+    );
+    assert!(!mailbox.read_only);
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn examine() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 EXAMINE blurdybloop"),
+        S("* 17 EXISTS"),
+        S("* 2 RECENT"),
+        S("a0 OK [READ-ONLY] EXAMINE completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let mailbox = client.examine("blurdybloop").await.unwrap();
+
+    assert_eq!(mailbox.exists, 17);
+    assert_eq!(mailbox.recent, 2);
+    assert!(mailbox.read_only);
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn capability() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 CAPABILITY"),
+        S("* CAPABILITY IMAP4rev1 STARTTLS AUTH=GSSAPI LOGINDISABLED"),
+        S("a0 OK CAPABILITY completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let capabilities = client.capability().await.unwrap();
+
+    assert_eq!(
+        capabilities,
+        &[
+            Capability::Imap4rev1,
+            Capability::StartTls,
+            Capability::Auth(AuthMechanism::Gssapi),
+            Capability::LoginDisabled,
+        ]
+        .iter()
+        .cloned()
+        .collect()
+    );
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn greeting_capabilities() {
+    let (addr, server) = serve(vec![
+        S("* OK [CAPABILITY IMAP4rev1 SASL-IR AUTH=PLAIN X-CUSTOM] Ready"),
+        C("a0 AUTHENTICATE PLAIN AHVzZXIAcGFzcw=="),
+        S("* CAPABILITY IMAP4rev1 MOVE"),
+        S("a0 OK Success"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let capabilities = client.capabilities().await.unwrap();
+    assert!(capabilities.contains(&Capability::Other("X-CUSTOM".to_owned())));
+    assert_eq!(
+        capabilities.auth_mechanisms().collect::<Vec<_>>(),
+        [&AuthMechanism::Plain]
+    );
+
+    client
+        .authenticate(sasl::Plain::new("user", "pass"))
+        .await
+        .unwrap();
+
+    let capabilities = client.capabilities().await.unwrap();
+    assert!(capabilities.contains(&Capability::Move));
+    assert!(!capabilities.contains(&Capability::SaslIr));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn append() {
+    let message = b"Subject: afternoon meeting\r\n\r\nHello Joe\r\n";
+
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 APPEND saved-messages (\\Seen) \" 7-Feb-1994 21:52:25 -0800\" {41}"),
+        S("+ Ready for literal data"),
+        C("Subject: afternoon meeting"),
+        C(""),
+        C("Hello Joe"),
+        C(""),
+        S("a0 OK [READ-WRITE] APPEND completed"),
+    ])
+    .await;
+
+    let date = DateTime {
+        day: 7,
+        month: Month::Feb,
+        year: 1994,
+        time: Time {
+            hours: 21,
+            minutes: 52,
+            seconds: 25,
+        },
+        zone: -800,
+    };
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let text = client
+        .append(
+            "saved-messages",
+            &[Flag::Defined(DefinedFlag::Seen)],
+            Some(&date),
+            message.len() as u32,
+            &message[..],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(text.text, "APPEND completed");
+    assert_eq!(text.code, vec![RespTextCode::ReadWrite]);
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn append_uid() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 APPEND Drafts {5}"),
+        S("+ Ready for literal data"),
+        C("Hello"),
+        S("a0 OK [APPENDUID 38505 3955] APPEND completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let text = client
+        .append("Drafts", &[], None, 5, &b"Hello"[..])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        text.append_uid(),
+        Some(&AppendUid {
+            uid_validity: 38505,
+            uids: SequenceSet::single(3955),
+        })
+    );
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn append_short_body() {
+    let (addr, stream) = listen().await;
+    let server = tokio::spawn(async move {
+        let script = [
+            GREETING,
+            C("a0 APPEND Drafts {10}"),
+            S("+ Ready for literal data"),
+        ];
+        let mut stream = play(stream.await.unwrap(), &script).await;
+
+        // Nothing is sent after the incomplete literal
+        let mut rest = vec![];
+        stream.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, b"short");
+    });
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let result = client.append("Drafts", &[], None, 10, &b"short"[..]).await;
+    assert!(matches!(result, Err(Error::Io(_))));
+
+    let result = client.select("INBOX").await;
+    assert!(matches!(result, Err(Error::ConnectionLost)));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn unsolicited() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 CAPABILITY"),
+        S("* CAPABILITY IMAP4rev1"),
+        S("a0 OK CAPABILITY completed"),
+        S("* 3 EXISTS"),
+        S("* 1 EXPUNGE"),
+        C("a1 LOGOUT"),
+        S("* BYE IMAP4rev1 Server logging out"),
+        S("a1 OK LOGOUT completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut unsolicited = client.unsolicited();
+
+    client.capability().await.unwrap();
+    assert_eq!(
+        unsolicited.recv().await.unwrap(),
+        UntaggedResponse::Mailbox(MailboxData::Exists(3))
+    );
+    assert_eq!(
+        unsolicited.recv().await.unwrap(),
+        UntaggedResponse::Message(MessageData::Expunge(1))
+    );
+
+    client.logout().await.unwrap();
+    assert!(matches!(
+        unsolicited.recv().await.unwrap(),
+        UntaggedResponse::Bye(text) if text.text == "IMAP4rev1 Server logging out"
+    ));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn noop_updates() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 NOOP"),
+        S("* 22 EXPUNGE"),
+        S("* 23 EXISTS"),
+        S("a0 OK NOOP completed"),
+        C("a1 STORE 1 +FLAGS (\\Seen)"),
+        S("* 1 FETCH (FLAGS (\\Seen))"),
+        S("* 24 EXISTS"),
+        S("a1 OK STORE completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut unsolicited = client.unsolicited();
+
+    client.noop().await.unwrap();
+    assert_eq!(
+        unsolicited.recv().await.unwrap(),
+        UntaggedResponse::Message(MessageData::Expunge(22))
+    );
+    assert_eq!(
+        unsolicited.recv().await.unwrap(),
+        UntaggedResponse::Mailbox(MailboxData::Exists(23))
+    );
+
+    // `FETCH` is consumed by `STORE`, `EXISTS` is not
+    let stored = client
+        .store(
+            &SequenceSet::single(1),
+            StoreMode::Add,
+            &[Flag::Defined(DefinedFlag::Seen)],
+            false,
+        )
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(
+        unsolicited.recv().await.unwrap(),
+        UntaggedResponse::Mailbox(MailboxData::Exists(24))
+    );
+    assert!(unsolicited.try_recv().is_err());
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn authenticate_plain_sasl_ir() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 CAPABILITY"),
+        S("* CAPABILITY IMAP4rev1 SASL-IR AUTH=PLAIN"),
+        S("a0 OK CAPABILITY completed"),
+        C("a1 AUTHENTICATE PLAIN AHVzZXIAcGFzcw=="),
+        S("a1 OK Success"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .authenticate(sasl::Plain::new("user", "pass"))
+        .await
+        .unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn authenticate_plain() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 CAPABILITY"),
+        S("* CAPABILITY IMAP4rev1 AUTH=PLAIN"),
+        S("a0 OK CAPABILITY completed"),
+        C("a1 AUTHENTICATE PLAIN"),
+        S("+ "),
+        C("AHVzZXIAcGFzcw=="),
+        S("a1 OK Success"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .authenticate(sasl::Plain::new("user", "pass"))
+        .await
+        .unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn authenticate_login() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 AUTHENTICATE LOGIN"),
+        S("+ VXNlcm5hbWU6"),
+        C("dXNlcg=="),
+        S("+ UGFzc3dvcmQ6"),
+        C("cGFzcw=="),
+        S("a0 OK Success"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .authenticate(sasl::Login::new("user", "pass"))
+        .await
+        .unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn authenticate_xoauth2_failed() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 CAPABILITY"),
+        S("* CAPABILITY IMAP4rev1 SASL-IR AUTH=XOAUTH2"),
+        S("a0 OK CAPABILITY completed"),
+        C("a1 AUTHENTICATE XOAUTH2 dXNlcj11c2VyAWF1dGg9QmVhcmVyIHRva2VuAQE="),
+        S("+ eyJzdGF0dXMiOiI0MDEifQ=="),
+        C(""),
+        S("a1 NO Invalid credentials"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let result = client
+        .authenticate(sasl::XOAuth2::new("user", "token"))
+        .await;
+
+    assert!(matches!(result, Err(Error::No(text)) if text == "Invalid credentials"));
+    server.await.unwrap();
+}
+
+// Server side of SCRAM-SHA-256 for "user" with "pencil". The server
+// signature is sent only if `verify` is set
+async fn scram_server(stream: TcpStream, verify: bool) {
+    let script = [
+        GREETING,
+        C("a0 CAPABILITY"),
+        S("* CAPABILITY IMAP4rev1 SASL-IR AUTH=SCRAM-SHA-256"),
+        S("a0 OK CAPABILITY completed"),
+    ];
+    let mut stream = BufReader::new(play(stream, &script).await);
+    let mut line = String::new();
+
+    stream.read_line(&mut line).await.unwrap();
+    let client_first = line
+        .trim_end()
+        .strip_prefix("a1 AUTHENTICATE SCRAM-SHA-256 ")
+        .unwrap();
+    let client_first = String::from_utf8(BASE64.decode(client_first).unwrap()).unwrap();
+    let bare = client_first.strip_prefix("n,,").unwrap().to_owned();
+    let nonce = bare.split_once(",r=").unwrap().1;
+
+    let server_first = format!("r={}server,s={},i=4096", nonce, BASE64.encode(b"salt"));
+    let challenge = format!("+ {}\r\n", BASE64.encode(&server_first));
+    stream.write_all(challenge.as_bytes()).await.unwrap();
+
+    line.clear();
+    stream.read_line(&mut line).await.unwrap();
+    let client_final = String::from_utf8(BASE64.decode(line.trim_end()).unwrap()).unwrap();
+    let without_proof = client_final.split_once(",p=").unwrap().0;
+
+    if verify {
+        let auth_message = format!("{},{},{}", bare, server_first, without_proof);
+        let mut salted = [0; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(b"pencil", b"salt", 4096, &mut salted);
+        let hmac = |key: &[u8], data: &[u8]| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+            mac.update(data);
+            mac.finalize().into_bytes()
+        };
+        let signature = hmac(&hmac(&salted, b"Server Key"), auth_message.as_bytes());
+
+        let verifier = format!(
+            "+ {}\r\n",
+            BASE64.encode(format!("v={}", BASE64.encode(signature)))
+        );
+        stream.write_all(verifier.as_bytes()).await.unwrap();
+        line.clear();
+        stream.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "\r\n");
+    }
+
+    stream.write_all(b"a1 OK Success\r\n").await.unwrap();
+}
+
+#[tokio::test]
+async fn authenticate_scram() {
+    let (addr, stream) = listen().await;
+    let server = tokio::spawn(async move { scram_server(stream.await.unwrap(), true).await });
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .authenticate(sasl::Scram::sha256("user", "pencil"))
+        .await
+        .unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn authenticate_scram_without_signature() {
+    let (addr, stream) = listen().await;
+    let server = tokio::spawn(async move { scram_server(stream.await.unwrap(), false).await });
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let result = client
+        .authenticate(sasl::Scram::sha256("user", "pencil"))
+        .await;
+
+    assert!(matches!(result, Err(Error::Sasl(_))));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn search() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 SEARCH FLAGGED SINCE 1-Feb-1994 NOT FROM Smith"),
+        S("* SEARCH 2 84 882"),
+        S("a0 OK SEARCH completed"),
+        C("a1 UID SEARCH CHARSET UTF-8 SUBJECT {12}"),
+        S("+ Ready for literal data"),
+        C("Привет"),
+        S("* SEARCH"),
+        S("a1 OK SEARCH completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let query = SearchQuery::Flagged
+        .and(SearchQuery::Since(Date::new(1994, Month::Feb, 1)))
+        .and(SearchQuery::From("Smith".to_owned()).not());
+    assert_eq!(client.search(&query).await.unwrap(), [2, 84, 882]);
+
+    let query = SearchQuery::Subject("Привет".to_owned());
+    assert!(client.uid_search(&query).await.unwrap().is_empty());
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn fetch() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 FETCH 1:2 (FLAGS BODY.PEEK[HEADER.FIELDS (Subject)])"),
+        S("* 1 FETCH (FLAGS (\\Seen) BODY[HEADER.FIELDS (\"Subject\")] {12}"),
+        S("Subject: a"),
+        S(")"),
+        S("* 3 EXISTS"),
+        S("* 2 FETCH (FLAGS () BODY[HEADER.FIELDS (\"Subject\")] NIL)"),
+        S("a0 OK FETCH completed"),
+        C("a1 UID FETCH 10:* FAST"),
+        S("* 1 FETCH (UID 10 FLAGS (\\Seen) INTERNALDATE \"17-Jul-1996 02:44:25 -0700\" RFC822.SIZE 4286)"),
+        S("a1 NO FETCH failed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let section = Section {
+        part: vec![],
+        text: Some(SectionText::HeaderFields(vec!["Subject".to_owned()])),
+    };
+    let items = FetchItems::from(vec![
+        FetchItem::Flags,
+        FetchItem::body_peek(section.clone()),
+    ]);
+    let messages = client
+        .fetch(&SequenceSet::range(1, 2), &items)
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(messages.len(), 2);
+    let first = messages[0].as_ref().unwrap();
+    assert_eq!(first.message, 1);
+    assert_eq!(first.flags, Some(vec![Flag::Defined(DefinedFlag::Seen)]));
+    assert_eq!(first.section(&section), Some(&b"Subject: a\r\n"[..]));
+    let second = messages[1].as_ref().unwrap();
+    assert_eq!(second.flags, Some(vec![]));
+    assert_eq!(second.section(&section), None);
+
+    let mut messages = client
+        .uid_fetch(
+            &SequenceSet::range(10, SeqNumber::Largest),
+            &FetchItems::Fast,
+        )
+        .await
+        .unwrap();
+    let message = messages.next().await.unwrap().unwrap();
+    assert_eq!((message.uid, message.size), (Some(10), Some(4286)));
+    assert!(matches!(messages.next().await, Some(Err(Error::No(_)))));
+    assert!(messages.next().await.is_none());
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn fetch_unparsable() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 UID FETCH 7:8 UID"),
+        S("* 1 FETCH (UID 7 X-UNKNOWN-ITEM 1)"),
+        S("* 2 FETCH (UID 8)"),
+        S("a0 OK FETCH completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let messages = client
+        .uid_fetch(&SequenceSet::range(7, 8), &FetchItem::Uid.into())
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+
+    // The caller learns that the first message is missing
+    assert_eq!(messages.len(), 2);
+    assert!(matches!(&messages[0], Err(Error::Parser(line)) if line.starts_with(b"* 1 FETCH")));
+    assert_eq!(messages[1].as_ref().unwrap().uid, Some(8));
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn fetch_dropped() {
+    let responses: String = (1..=100)
+        .map(|n| format!("* {} FETCH (UID {})\r\n", n, n))
+        .collect();
+    let responses = format!("{}a0 OK FETCH completed", responses);
+
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 FETCH 1:* UID"),
+        S(Box::leak(responses.into_boxed_str())),
+        C("a1 SELECT INBOX"),
+        S("* 100 EXISTS"),
+        S("a1 OK [READ-WRITE] SELECT completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    // The stream borrows the client until it is dropped, the rest of
+    // the responses must not block the next command
+    let messages = client
+        .fetch(&SequenceSet::all(), &FetchItem::Uid.into())
+        .await
+        .unwrap();
+    drop(messages);
+
+    let mailbox = tokio::time::timeout(Duration::from_secs(2), client.select("INBOX"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(mailbox.exists, 100);
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn store() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 STORE 2:4 +FLAGS (\\Deleted $Junk)"),
+        S("* 2 FETCH (FLAGS (\\Deleted \\Seen $Junk))"),
+        S("* 4 FETCH (FLAGS (\\Deleted $Junk))"),
+        S("a0 OK STORE completed"),
+        C("a1 UID STORE 7 FLAGS.SILENT ()"),
+        S("a1 OK STORE completed"),
+        C("a2 UID STORE 8 -FLAGS (\\Seen)"),
+        S("a2 NO [CANNOT] Mailbox is read-only"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let deleted = Flag::Defined(DefinedFlag::Deleted);
+    let junk = Flag::Keyword("$Junk".to_owned());
+
+    let messages = client
+        .store(
+            &SequenceSet::range(2, 4),
+            StoreMode::Add,
+            &[deleted.clone(), junk.clone()],
+            false,
+        )
+        .await
+        .unwrap();
+    let flags: Vec<_> = messages
+        .iter()
+        .map(|v| (v.message, v.flags.clone()))
+        .collect();
+    assert_eq!(
+        flags,
+        [
+            (
+                2,
+                Some(vec![
+                    deleted.clone(),
+                    Flag::Defined(DefinedFlag::Seen),
+                    junk.clone()
+                ])
+            ),
+            (4, Some(vec![deleted, junk]))
+        ]
+    );
+
+    let messages = client
+        .uid_store(&SequenceSet::single(7), StoreMode::Replace, &[], true)
+        .await
+        .unwrap();
+    assert!(messages.is_empty());
+
+    let result = client
+        .uid_store(
+            &SequenceSet::single(8),
+            StoreMode::Remove,
+            &[Flag::Defined(DefinedFlag::Seen)],
+            false,
+        )
+        .await;
+    assert!(matches!(result, Err(Error::No(_))));
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn mailbox_management() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 CREATE \"Work Items\""),
+        S("a0 OK CREATE completed"),
+        C("a1 RENAME \"Work Items\" Archive"),
+        S("a1 OK RENAME completed"),
+        C("a2 SUBSCRIBE Archive"),
+        S("a2 OK SUBSCRIBE completed"),
+        C("a3 LIST \"\" %"),
+        S("* LIST (\\HasNoChildren) \"/\" INBOX"),
+        S("* LIST (\\Noselect \\HasChildren) \"/\" \"My \\\"Folder\\\"\""),
+        S("* LIST () NIL Archive"),
+        S("a3 OK LIST completed"),
+        C("a4 LSUB \"\" *"),
+        S("* LSUB () \"/\" Archive"),
+        S("a4 OK LSUB completed"),
+        C("a5 UNSUBSCRIBE Archive"),
+        S("a5 OK UNSUBSCRIBE completed"),
+        C("a6 DELETE Archive"),
+        S("a6 NO Mailbox has inferior hierarchical names"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client.create("Work Items").await.unwrap();
+    client.rename("Work Items", "Archive").await.unwrap();
+    client.subscribe("Archive").await.unwrap();
+
+    let mailboxes = client.list("", "%").await.unwrap();
+    assert_eq!(
+        mailboxes,
+        [
+            MailboxInfo {
+                flags: vec![ListFlag::Extension("\\HasNoChildren".to_owned())],
+                delimiter: Some("/".to_owned()),
+                name: "INBOX".to_owned(),
+            },
+            MailboxInfo {
+                flags: vec![
+                    ListFlag::Defined(ListDefinedFlag::Noselect),
+                    ListFlag::Extension("\\HasChildren".to_owned())
+                ],
+                delimiter: Some("/".to_owned()),
+                name: "My \"Folder\"".to_owned(),
+            },
+            MailboxInfo {
+                flags: vec![],
+                delimiter: None,
+                name: "Archive".to_owned(),
+            },
+        ]
+    );
+
+    let subscribed = client.lsub("", "*").await.unwrap();
+    assert_eq!(subscribed.len(), 1);
+    assert_eq!(subscribed[0].name, "Archive");
+
+    client.unsubscribe("Archive").await.unwrap();
+    let result = client.delete("Archive").await;
+    assert!(matches!(result, Err(Error::No(_))));
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn status() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 STATUS blurdybloop (MESSAGES UIDNEXT UNSEEN)"),
+        S("* STATUS blurdybloop (MESSAGES 231 UIDNEXT 44292 UNSEEN 0)"),
+        S("a0 OK STATUS completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let status = client
+        .status(
+            "blurdybloop",
+            &[
+                StatusAttribute::Messages,
+                StatusAttribute::UidNext,
+                StatusAttribute::Unseen,
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        status,
+        MailboxStatus {
+            name: "blurdybloop".to_owned(),
+            status: vec![
+                StatusInfo::Messages(231),
+                StatusInfo::UidNext(44292),
+                StatusInfo::Unseen(0)
+            ],
+        }
+    );
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn copy_and_expunge() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 COPY 2:4 MEETING"),
+        S("a0 OK [COPYUID 38505 304,319:320 3956:3958] Done"),
+        C("a1 UID COPY 4827313:4828442 Archive"),
+        S("a1 NO [TRYCREATE] Mailbox doesn't exist"),
+        C("a2 EXPUNGE"),
+        S("* 3 EXPUNGE"),
+        S("* 3 EXPUNGE"),
+        S("* 5 EXPUNGE"),
+        S("* 8 EXPUNGE"),
+        S("a2 OK EXPUNGE completed"),
+        C("a3 UID EXPUNGE 3000:3002"),
+        S("* 3 EXPUNGE"),
+        S("a3 OK UID EXPUNGE completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let copy_uid = client
+        .copy(&SequenceSet::range(2, 4), "MEETING")
+        .await
+        .unwrap();
+    assert_eq!(
+        copy_uid,
+        Some(CopyUid {
+            uid_validity: 38505,
+            source: vec![304, 319, 320],
+            destination: vec![3956, 3957, 3958],
+        })
+    );
+    let result = client
+        .uid_copy(&SequenceSet::range(4827313, 4828442), "Archive")
+        .await;
+    assert!(matches!(result, Err(Error::No(_))));
+
+    assert_eq!(client.expunge().await.unwrap().messages, [3, 3, 5, 8]);
+    assert_eq!(
+        client
+            .uid_expunge(&SequenceSet::range(3000, 3002))
+            .await
+            .unwrap()
+            .messages,
+        [3]
+    );
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn move_messages() {
+    let (addr, server) = serve(vec![
+        S("* OK [CAPABILITY IMAP4rev1 MOVE] Service Ready"),
+        C("a0 UID MOVE 42:69 foo"),
+        S("* OK [COPYUID 432432 42:69 1202:1229]"),
+        S("* 22 EXPUNGE"),
+        S("* 22 EXPUNGE"),
+        S("a0 OK Done"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let moved = client
+        .uid_mv(&SequenceSet::range(42, 69), "foo")
+        .await
+        .unwrap();
+    assert_eq!(
+        moved,
+        Moved {
+            copy_uid: Some(CopyUid {
+                uid_validity: 432432,
+                source: (42..=69).collect(),
+                destination: (1202..=1229).collect(),
+            }),
+            expunged: vec![22, 22],
+            vanished: SequenceSet::new(),
+        }
+    );
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn move_messages_fallback() {
+    let (addr, server) = serve(vec![
+        S("* OK [CAPABILITY IMAP4rev1 UIDPLUS] Service Ready"),
+        C("a0 FETCH 2:3 UID"),
+        S("* 2 FETCH (UID 10)"),
+        S("* 3 FETCH (UID 12)"),
+        S("a0 OK FETCH completed"),
+        C("a1 UID COPY 10,12 Trash"),
+        S("a1 OK COPY completed"),
+        C("a2 UID STORE 10,12 +FLAGS.SILENT (\\Deleted)"),
+        S("a2 OK STORE completed"),
+        C("a3 UID EXPUNGE 10,12"),
+        S("* 2 EXPUNGE"),
+        S("* 2 EXPUNGE"),
+        S("a3 OK UID EXPUNGE completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let moved = client.mv(&SequenceSet::range(2, 3), "Trash").await.unwrap();
+    assert_eq!(moved.copy_uid, None);
+    assert_eq!(moved.expunged, [2, 2]);
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn move_messages_unsupported() {
+    let (addr, server) = serve(vec![
+        S("* OK [CAPABILITY IMAP4rev1] Service Ready"),
+        C("a0 NOOP"),
+        S("a0 OK NOOP completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let result = client.mv(&SequenceSet::range(2, 3), "Trash").await;
+    assert!(matches!(result, Err(Error::Unsupported(_))));
+    // Nothing was sent for the move
+    client.noop().await.unwrap();
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn vanished() {
+    let (addr, server) = serve(vec![
+        S("* OK [CAPABILITY IMAP4rev1 MOVE UIDPLUS QRESYNC] Service Ready"),
+        C("a0 EXPUNGE"),
+        S("* VANISHED 405,407,410:425"),
+        S("a0 OK Expunged"),
+        C("a1 UID EXPUNGE 3000:3002"),
+        S("* VANISHED 3000"),
+        S("* VANISHED 3002"),
+        S("a1 OK Expunged"),
+        C("a2 UID MOVE 42:69 foo"),
+        S("* OK [COPYUID 432432 42:69 1202:1229]"),
+        S("* VANISHED 42:69"),
+        S("a2 OK Done"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let expunged = client.expunge().await.unwrap();
+    assert!(expunged.messages.is_empty());
+    assert_eq!(expunged.vanished.to_string(), "405,407,410:425");
+
+    let expunged = client
+        .uid_expunge(&SequenceSet::range(3000, 3002))
+        .await
+        .unwrap();
+    assert_eq!(expunged.vanished.to_string(), "3000,3002");
+
+    let moved = client
+        .uid_mv(&SequenceSet::range(42, 69), "foo")
+        .await
+        .unwrap();
+    assert!(moved.expunged.is_empty());
+    assert_eq!(moved.vanished, SequenceSet::range(42, 69));
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn condstore() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 SELECT INBOX"),
+        S("* 172 EXISTS"),
+        S("* OK [UIDVALIDITY 3857529045] UIDs valid"),
+        S("* OK [HIGHESTMODSEQ 715194045007] Highest"),
+        S("a0 OK [READ-WRITE] SELECT completed"),
+        C("a1 UID FETCH 1:* FLAGS (CHANGEDSINCE 12345)"),
+        S("* 1 FETCH (UID 4 MODSEQ (65402) FLAGS (\\Seen))"),
+        S("a1 OK FETCH completed"),
+        C("a2 UID STORE 5,7,9 (UNCHANGEDSINCE 320162338) +FLAGS.SILENT (\\Deleted)"),
+        S("* 3 FETCH (UID 5 MODSEQ (320162342))"),
+        S("a2 OK [MODIFIED 7,9] Conditional STORE failed"),
+        C("a3 SEARCH MODSEQ 620162338"),
+        S("* SEARCH 2 5 6 (MODSEQ 917162500)"),
+        S("a3 OK Search complete"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let mailbox = client.select("INBOX").await.unwrap();
+    assert_eq!(mailbox.highest_modseq, Some(715194045007));
+
+    let messages = client
+        .uid_fetch_changed_since(&SequenceSet::all(), &FetchItem::Flags.into(), 12345)
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    let message = messages[0].as_ref().unwrap();
+    assert_eq!((message.uid, message.modseq), (Some(4), Some(65402)));
+
+    let stored = client
+        .uid_store_unchanged_since(
+            &"5,7,9".parse().unwrap(),
+            320162338,
+            StoreMode::Add,
+            &[Flag::Defined(DefinedFlag::Deleted)],
+            true,
+        )
+        .await
+        .unwrap();
+    assert_eq!(stored.messages[0].modseq, Some(320162342));
+    assert_eq!(stored.modified.to_string(), "7,9");
+
+    let found = client
+        .search_modseq(&SearchQuery::ModSeq(620162338))
+        .await
+        .unwrap();
+    assert_eq!(found.numbers, [2, 5, 6]);
+    assert_eq!(found.modseq, Some(917162500));
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn qresync() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 ENABLE QRESYNC"),
+        S("* ENABLED QRESYNC"),
+        S("a0 OK Enabled"),
+        C("a1 SELECT INBOX (QRESYNC (67890007 20050715194045000 41,43:211,214:541))"),
+        S("* 314 EXISTS"),
+        S("* OK [UIDVALIDITY 67890007] UIDVALIDITY"),
+        S("* OK [HIGHESTMODSEQ 20050715194045319] Highest"),
+        S("* VANISHED (EARLIER) 41,43:116,118,120:211"),
+        S("* 49 FETCH (UID 117 FLAGS (\\Seen \\Answered) MODSEQ (20050715194045000))"),
+        S("a1 OK [READ-WRITE] mailbox selected"),
+        C("a2 SELECT Archive (QRESYNC (12 500))"),
+        S("* 2 EXISTS"),
+        S("* OK [UIDVALIDITY 12] UIDVALIDITY"),
+        S("* VANISHED (EARLIER) 3"),
+        S("a2 OK [READ-WRITE] mailbox selected"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let resync = client
+        .select_qresync(
+            "INBOX",
+            67890007,
+            20050715194045000,
+            Some(&"41,43:211,214:541".parse().unwrap()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resync.mailbox.exists, 314);
+    assert_eq!(resync.mailbox.highest_modseq, Some(20050715194045319));
+    assert_eq!(resync.vanished.to_string(), "41,43:116,118,120:211");
+    assert_eq!(resync.changed[0].uid, Some(117));
+    assert_eq!(resync.changed[0].modseq, Some(20050715194045000));
+
+    // QRESYNC is enabled only once per session
+    let resync = client
+        .select_qresync("Archive", 12, 500, None)
+        .await
+        .unwrap();
+    assert_eq!(resync.vanished, SequenceSet::single(3));
+    assert!(resync.changed.is_empty());
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn qresync_not_enabled() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 ENABLE QRESYNC"),
+        S("* ENABLED"),
+        S("a0 OK Nothing enabled"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let result = client.select_qresync("INBOX", 67890007, 1, None).await;
+    assert!(matches!(result, Err(Error::Unsupported(_))));
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn enable_and_id() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 ENABLE CONDSTORE X-GOOD-IDEA"),
+        S("* ENABLED CONDSTORE"),
+        S("a0 OK Enabled"),
+        C("a1 ID (\"name\" \"rimap\")"),
+        S("* ID (\"name\" \"Cyrus\" \"vendor\" NIL)"),
+        S("a1 OK Success"),
+        C("a2 ID NIL"),
+        S("* ID NIL"),
+        S("a2 OK Success"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let enabled = client
+        .enable(&[
+            Capability::CondStore,
+            Capability::Other("X-GOOD-IDEA".to_owned()),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(enabled.iter().collect::<Vec<_>>(), [&Capability::CondStore]);
+    assert!(client.enabled().contains(&Capability::CondStore));
+
+    let fields = client.id(&[("name", "rimap")]).await.unwrap();
+    assert_eq!(fields["name"].as_deref(), Some("Cyrus"));
+    assert_eq!(fields["vendor"], None);
+
+    let fields = client.id(&[]).await.unwrap();
+    assert!(fields.is_empty());
+
+    server.await.unwrap();
+}
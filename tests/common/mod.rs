@@ -0,0 +1,69 @@
+//! Scripted IMAP server for client tests
+
+#![allow(dead_code)]
+
+use std::net::SocketAddr;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+/// One step of the server script
+#[derive(Debug, Clone, Copy)]
+pub enum Step {
+    /// Expect the line from the client, without CRLF
+    C(&'static str),
+    /// Send the line to the client, CRLF is appended
+    S(&'static str),
+}
+
+/// Accept one connection and play `script` on it. The returned handle
+/// panics if the client deviates from the script
+pub async fn serve(script: Vec<Step>) -> (SocketAddr, JoinHandle<()>) {
+    let (addr, listener) = listen().await;
+
+    let handle = tokio::spawn(async move {
+        let stream = listener.await.unwrap();
+        play(stream, &script).await;
+    });
+
+    (addr, handle)
+}
+
+/// Bind on a random local port, the handle resolves to the accepted stream
+pub async fn listen() -> (SocketAddr, JoinHandle<TcpStream>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let handle = tokio::spawn(async move { listener.accept().await.unwrap().0 });
+
+    (addr, handle)
+}
+
+/// Play `script` on the stream and give the stream back
+pub async fn play<S>(stream: S, script: &[Step]) -> S
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+
+    for step in script {
+        match *step {
+            Step::C(expected) => {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                assert_eq!(line, format!("{}\r\n", expected));
+            }
+            Step::S(response) => {
+                writer
+                    .write_all(format!("{}\r\n", response).as_bytes())
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
+    reader.into_inner().unsplit(writer)
+}
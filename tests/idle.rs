@@ -0,0 +1,157 @@
+mod common;
+
+use common::{serve, Step, Step::*};
+use futures::StreamExt;
+use rimap::{
+    client::{Client, IdleEvent},
+    error::Error,
+    types::{DefinedFlag, Flag, MailboxData, MessageData, MsgAtt, UntaggedResponse},
+};
+use std::time::Duration;
+
+const GREETING: Step = S("* OK IMAP4rev1 Service Ready");
+
+#[tokio::test]
+async fn idle_events() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 IDLE"),
+        S("+ idling"),
+        S("* 4 EXISTS"),
+        S("* OK Still here"),
+        S("* 2 EXPUNGE"),
+        S("* 3 FETCH (FLAGS (\\Seen))"),
+        C("DONE"),
+        S("a0 OK IDLE terminated"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut idle = client.idle().await.unwrap();
+
+    let mut events = vec![];
+    for _ in 0..3 {
+        events.push(idle.next().await.unwrap().unwrap());
+    }
+    idle.done().await.unwrap();
+
+    assert_eq!(
+        events,
+        vec![
+            IdleEvent::Mailbox(MailboxData::Exists(4)),
+            IdleEvent::Message(MessageData::Expunge(2)),
+            IdleEvent::Message(MessageData::Fetch(
+                3,
+                vec![MsgAtt::Flags(vec![Flag::Defined(DefinedFlag::Seen)])]
+            )),
+        ]
+    );
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn idle_vanished() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 IDLE"),
+        S("+ idling"),
+        S("* VANISHED 405,407,410:425"),
+        C("DONE"),
+        S("a0 OK IDLE terminated"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut idle = client.idle().await.unwrap();
+
+    let event = idle.next().await.unwrap().unwrap();
+    idle.done().await.unwrap();
+
+    assert_eq!(
+        event,
+        IdleEvent::Vanished("405,407,410:425".parse().unwrap())
+    );
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn idle_reissue() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 IDLE"),
+        S("+ idling"),
+        C("DONE"),
+        S("* 5 EXISTS"),
+        S("a0 OK IDLE terminated"),
+        C("a1 IDLE"),
+        S("+ idling"),
+        S("* 1 RECENT"),
+        C("DONE"),
+        S("a1 OK IDLE terminated"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut idle = client
+        .idle()
+        .await
+        .unwrap()
+        .with_timeout(Duration::from_millis(50));
+
+    let first = idle.next().await.unwrap().unwrap();
+    let second = idle.next().await.unwrap().unwrap();
+    idle.done().await.unwrap();
+
+    assert_eq!(first, IdleEvent::Mailbox(MailboxData::Exists(5)));
+    assert_eq!(second, IdleEvent::Mailbox(MailboxData::Recent(1)));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn idle_bye() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 IDLE"),
+        S("+ idling"),
+        S("* BYE Autologout; idle for too long"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut idle = client.idle().await.unwrap();
+
+    let result = idle.next().await.unwrap();
+
+    assert!(matches!(result, Err(Error::Bye(_))));
+    assert!(idle.next().await.is_none());
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn idle_dropped() {
+    let (addr, server) = serve(vec![
+        GREETING,
+        C("a0 IDLE"),
+        S("+ idling"),
+        C("DONE"),
+        S("* 2 EXISTS"),
+        S("a0 OK IDLE terminated"),
+        C("a1 CAPABILITY"),
+        S("* CAPABILITY IMAP4rev1"),
+        S("a1 OK CAPABILITY completed"),
+    ])
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut unsolicited = client.unsolicited();
+    drop(client.idle().await.unwrap());
+
+    // `IDLE` is finished before the next command
+    client.capability().await.unwrap();
+
+    assert_eq!(
+        unsolicited.recv().await.unwrap(),
+        UntaggedResponse::Mailbox(MailboxData::Exists(2))
+    );
+    server.await.unwrap();
+}
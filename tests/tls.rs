@@ -0,0 +1,157 @@
+#![cfg(feature = "rustls-tls")]
+
+mod common;
+
+use common::{listen, play, Step::*};
+use rimap::{
+    client::Client,
+    error::Error,
+    tls::tokio_rustls::{
+        rustls::{
+            crypto::ring,
+            pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer},
+            ClientConfig, RootCertStore, ServerConfig,
+        },
+        TlsAcceptor, TlsConnector,
+    },
+    types::{AuthMechanism, Capability},
+};
+use std::sync::Arc;
+
+// Self-signed certificate for `localhost` trusted by the client
+fn tls_pair() -> (TlsAcceptor, TlsConnector) {
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+    let cert = certified.cert.der().clone();
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der()));
+    let provider = Arc::new(ring::default_provider());
+
+    let server = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.clone()], key)
+        .unwrap();
+
+    let mut roots = RootCertStore::empty();
+    roots.add(cert).unwrap();
+    let client = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    (
+        TlsAcceptor::from(Arc::new(server)),
+        TlsConnector::from(Arc::new(client)),
+    )
+}
+
+#[tokio::test]
+async fn implicit_tls() {
+    let (acceptor, connector) = tls_pair();
+    let (addr, listener) = listen().await;
+
+    let server = tokio::spawn(async move {
+        let stream = acceptor.accept(listener.await.unwrap()).await.unwrap();
+        let exported = stream
+            .get_ref()
+            .1
+            .export_keying_material([0; 32], b"EXPORTER-Channel-Binding", None)
+            .unwrap();
+        play(
+            stream,
+            &[
+                S("* OK IMAP4rev1 Service Ready"),
+                C("a0 CAPABILITY"),
+                S("* CAPABILITY IMAP4rev1 AUTH=PLAIN"),
+                S("a0 OK CAPABILITY completed"),
+            ],
+        )
+        .await;
+        exported
+    });
+
+    let mut client = Client::connect_tls(addr, "localhost", &connector)
+        .await
+        .unwrap();
+    let capabilities = client.capability().await.unwrap();
+
+    assert_eq!(
+        capabilities,
+        &[
+            Capability::Imap4rev1,
+            Capability::Auth(AuthMechanism::Plain)
+        ]
+        .iter()
+        .cloned()
+        .collect()
+    );
+
+    let exported = server.await.unwrap();
+    let binding = client.channel_binding().unwrap();
+    assert_eq!(binding.name, "tls-exporter");
+    assert_eq!(binding.data, exported);
+}
+
+#[tokio::test]
+async fn starttls() {
+    let (acceptor, connector) = tls_pair();
+    let (addr, listener) = listen().await;
+
+    let server = tokio::spawn(async move {
+        let stream = play(
+            listener.await.unwrap(),
+            &[
+                S("* OK [CAPABILITY IMAP4rev1 STARTTLS LOGINDISABLED] Ready"),
+                C("a0 STARTTLS"),
+                S("a0 OK Begin TLS negotiation now"),
+            ],
+        )
+        .await;
+
+        let stream = acceptor.accept(stream).await.unwrap();
+        play(
+            stream,
+            &[
+                C("a1 CAPABILITY"),
+                S("* CAPABILITY IMAP4rev1 AUTH=PLAIN"),
+                S("a1 OK CAPABILITY completed"),
+                C("a2 LOGIN user pass"),
+                S("a2 OK [CAPABILITY IMAP4rev1 IDLE] LOGIN completed"),
+            ],
+        )
+        .await;
+    });
+
+    let client = Client::connect(addr).await.unwrap();
+    assert_eq!(client.channel_binding(), None);
+    let mut client = client.starttls("localhost", &connector).await.unwrap();
+    assert_eq!(client.channel_binding().unwrap().name, "tls-exporter");
+
+    // Capabilities from the greeting are discarded after STARTTLS
+    let capabilities = client.capabilities().await.unwrap();
+    assert!(!capabilities.contains(&Capability::StartTls));
+    assert!(capabilities.supports_auth(&AuthMechanism::Plain));
+
+    client.login("user", "pass").await.unwrap();
+    let capabilities = client.capabilities().await.unwrap();
+    assert!(capabilities.contains(&Capability::Idle));
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn certificate_mismatch() {
+    let (acceptor, connector) = tls_pair();
+    let (addr, listener) = listen().await;
+
+    let server = tokio::spawn(async move {
+        // Handshake is aborted by the client
+        let _ = acceptor.accept(listener.await.unwrap()).await;
+    });
+
+    let result = Client::connect_tls(addr, "imap.example.com", &connector).await;
+
+    assert!(matches!(result, Err(Error::Io(_))));
+    server.await.unwrap();
+}